@@ -0,0 +1,665 @@
+use core::{cmp::Ordering, marker::PhantomData};
+
+use heapless::Vec;
+#[allow(unused_imports)]
+use micromath::F32Ext;
+use serde::{Deserialize, Serialize};
+use uom::si::f32::{
+    Acceleration, AngularAcceleration, AngularJerk, AngularVelocity, ElectricPotential, Jerk,
+    Length, Time, Velocity,
+};
+
+use crate::{
+    control::{
+        motor::MotorModel, AntiWindup, ControlParameters, Controller, NavigationController,
+        SupervisoryController, Tracker,
+    },
+    estimate::Estimator,
+    trajectory::{
+        slalom::{SlalomConfig, SlalomDirection, SlalomGenerator, SlalomKind},
+        spin::{PivotGenerator, SpinGenerator},
+        straight::{ProfileShape, StraightGenerator},
+    },
+    wall::{
+        Pose, PoseConverter, WallDetector, DEFAULT_IGNORE_LENGTH, DEFAULT_IGNORE_RADIUS,
+        DEFAULT_WALL_WIDTH,
+    },
+};
+
+/// The maximum number of distance sensors in [`RobotConfig`].
+pub const MAX_SENSORS: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConfigError {
+    /// The field must be positive.
+    NotPositive(&'static str),
+    /// The field is out of its range.
+    OutOfRange(&'static str),
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct EstimatorConfig {
+    pub alpha: f32,
+    pub slip_angle_const: Acceleration,
+    pub approx_th: AngularVelocity,
+}
+
+impl Default for EstimatorConfig {
+    fn default() -> Self {
+        Self {
+            alpha: 0.760_942_76,
+            slip_angle_const: Acceleration {
+                value: 100.0,
+                dimension: PhantomData,
+                units: PhantomData,
+            },
+            approx_th: AngularVelocity {
+                value: 0.1,
+                dimension: PhantomData,
+                units: PhantomData,
+            },
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct WallConfig {
+    pub wall_width: Length,
+    pub ignore_radius_from_pillar: Length,
+    pub ignore_length_from_wall: Length,
+}
+
+impl Default for WallConfig {
+    fn default() -> Self {
+        Self {
+            wall_width: DEFAULT_WALL_WIDTH,
+            ignore_radius_from_pillar: DEFAULT_IGNORE_RADIUS,
+            ignore_length_from_wall: DEFAULT_IGNORE_LENGTH,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TrackerConfig {
+    pub xi_threshold: Velocity,
+    pub zeta: f32,
+    pub b: f32,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct NavigationConfig {
+    pub gain: f32,
+    pub dgain: f32,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SupervisorConfig {
+    pub margin: f32,
+    pub avoidance_distance: Length,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ControllerConfig {
+    pub trans_params: ControlParameters,
+    pub rot_params: ControlParameters,
+    pub max_voltage: ElectricPotential,
+    /// The maximum absolute voltage of the translational channel, unlimited by default.
+    #[serde(default = "unlimited_voltage")]
+    pub max_trans_voltage: ElectricPotential,
+    /// The maximum absolute voltage of the rotational channel, unlimited by default.
+    #[serde(default = "unlimited_voltage")]
+    pub max_rot_voltage: ElectricPotential,
+    #[serde(default)]
+    pub anti_windup: AntiWindup,
+    #[serde(default)]
+    pub motor_model: Option<MotorModel>,
+}
+
+fn unlimited_voltage() -> ElectricPotential {
+    ElectricPotential {
+        value: f32::INFINITY,
+        dimension: PhantomData,
+        units: PhantomData,
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct StraightConfig {
+    pub v_max: Velocity,
+    pub a_accel: Acceleration,
+    pub j_accel: Jerk,
+    pub a_decel: Acceleration,
+    pub j_decel: Jerk,
+    #[serde(default)]
+    pub shape: ProfileShape,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SpinConfig {
+    pub max_angular_velocity: AngularVelocity,
+    pub max_angular_acceleration: AngularAcceleration,
+    pub max_angular_jerk: AngularJerk,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SlalomLimits {
+    pub v_max: Velocity,
+    pub a_max: Acceleration,
+    pub j_max: Jerk,
+}
+
+// Return an error unless `value` is positive, which also rejects NaN.
+fn check_positive(name: &'static str, value: f32) -> Result<(), ConfigError> {
+    if value.partial_cmp(&0.0) == Some(Ordering::Greater) {
+        Ok(())
+    } else {
+        Err(ConfigError::NotPositive(name))
+    }
+}
+
+// Return an error unless `value` is zero or positive, which also rejects NaN.
+fn check_non_negative(name: &'static str, value: f32) -> Result<(), ConfigError> {
+    if value.is_nan() || value < 0.0 {
+        Err(ConfigError::OutOfRange(name))
+    } else {
+        Ok(())
+    }
+}
+
+/// The whole configuration of a robot, from which every component is built.
+///
+/// Call [`RobotConfig::validate`] after deserializing, since the constructors assume valid values.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RobotConfig {
+    pub period: Time,
+    pub square_width: Length,
+    pub front_offset: Length,
+    pub wheel_interval: Length,
+    pub max_wheel_velocity: Velocity,
+    /// The poses of the distance sensors relative to the center of the robot.
+    pub sensor_poses: Vec<Pose, MAX_SENSORS>,
+    #[serde(default)]
+    pub wall: WallConfig,
+    #[serde(default)]
+    pub estimator: EstimatorConfig,
+    pub tracker: TrackerConfig,
+    pub navigation: NavigationConfig,
+    pub supervisor: SupervisorConfig,
+    pub controller: ControllerConfig,
+    pub straight: StraightConfig,
+    pub spin: SpinConfig,
+    pub slalom: SlalomLimits,
+    pub slalom_config: SlalomConfig,
+}
+
+impl RobotConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let positives = [
+            ("period", self.period.value),
+            ("square_width", self.square_width.value),
+            ("wheel_interval", self.wheel_interval.value),
+            ("max_wheel_velocity", self.max_wheel_velocity.value),
+            ("tracker.xi_threshold", self.tracker.xi_threshold.value),
+            ("tracker.zeta", self.tracker.zeta),
+            ("tracker.b", self.tracker.b),
+            ("controller.max_voltage", self.controller.max_voltage.value),
+            (
+                "controller.max_trans_voltage",
+                self.controller.max_trans_voltage.value,
+            ),
+            (
+                "controller.max_rot_voltage",
+                self.controller.max_rot_voltage.value,
+            ),
+            (
+                "controller.trans_params.model_k",
+                self.controller.trans_params.model_k,
+            ),
+            (
+                "controller.trans_params.model_t1",
+                self.controller.trans_params.model_t1,
+            ),
+            (
+                "controller.rot_params.model_k",
+                self.controller.rot_params.model_k,
+            ),
+            (
+                "controller.rot_params.model_t1",
+                self.controller.rot_params.model_t1,
+            ),
+            ("straight.v_max", self.straight.v_max.value),
+            ("straight.a_accel", self.straight.a_accel.value),
+            ("straight.j_accel", self.straight.j_accel.value),
+            ("straight.a_decel", self.straight.a_decel.value),
+            ("straight.j_decel", self.straight.j_decel.value),
+            (
+                "spin.max_angular_velocity",
+                self.spin.max_angular_velocity.value,
+            ),
+            (
+                "spin.max_angular_acceleration",
+                self.spin.max_angular_acceleration.value,
+            ),
+            ("spin.max_angular_jerk", self.spin.max_angular_jerk.value),
+            ("slalom.v_max", self.slalom.v_max.value),
+            ("slalom.a_max", self.slalom.a_max.value),
+            ("slalom.j_max", self.slalom.j_max.value),
+            ("wall.wall_width", self.wall.wall_width.value),
+            ("supervisor.margin", self.supervisor.margin),
+            (
+                "supervisor.avoidance_distance",
+                self.supervisor.avoidance_distance.value,
+            ),
+        ];
+        for (name, value) in positives {
+            check_positive(name, value)?;
+        }
+        if let Some(model) = &self.controller.motor_model {
+            let positives = [
+                ("controller.motor_model.mass", model.mass),
+                ("controller.motor_model.inertia", model.inertia),
+                (
+                    "controller.motor_model.wheel_interval",
+                    model.wheel_interval,
+                ),
+                (
+                    "controller.motor_model.left.resistance",
+                    model.left.resistance,
+                ),
+                (
+                    "controller.motor_model.left.back_emf_constant",
+                    model.left.back_emf_constant,
+                ),
+                (
+                    "controller.motor_model.left.torque_constant",
+                    model.left.torque_constant,
+                ),
+                (
+                    "controller.motor_model.left.wheel_radius",
+                    model.left.wheel_radius,
+                ),
+                (
+                    "controller.motor_model.left.gear_ratio",
+                    model.left.gear_ratio,
+                ),
+                (
+                    "controller.motor_model.right.resistance",
+                    model.right.resistance,
+                ),
+                (
+                    "controller.motor_model.right.back_emf_constant",
+                    model.right.back_emf_constant,
+                ),
+                (
+                    "controller.motor_model.right.torque_constant",
+                    model.right.torque_constant,
+                ),
+                (
+                    "controller.motor_model.right.wheel_radius",
+                    model.right.wheel_radius,
+                ),
+                (
+                    "controller.motor_model.right.gear_ratio",
+                    model.right.gear_ratio,
+                ),
+            ];
+            for (name, value) in positives {
+                check_positive(name, value)?;
+            }
+        }
+        for kind in SlalomKind::ALL {
+            for direction in [SlalomDirection::Left, SlalomDirection::Right] {
+                let params = self.slalom_config.parameters(kind, direction);
+                check_positive("slalom_config.v_ref", params.v_ref.value)?;
+                check_positive("slalom_config.dtheta", params.dtheta.value)?;
+                check_positive("slalom_config.ddtheta", params.ddtheta.value)?;
+                check_positive("slalom_config.dddtheta", params.dddtheta.value)?;
+                check_non_negative("slalom_config.l_start", params.l_start.value)?;
+                check_non_negative("slalom_config.l_end", params.l_end.value)?;
+            }
+        }
+        check_non_negative(
+            "wall.ignore_radius_from_pillar",
+            self.wall.ignore_radius_from_pillar.value,
+        )?;
+        check_non_negative(
+            "wall.ignore_length_from_wall",
+            self.wall.ignore_length_from_wall.value,
+        )?;
+        if !(0.0..1.0).contains(&self.estimator.alpha) {
+            return Err(ConfigError::OutOfRange("estimator.alpha"));
+        }
+        if self
+            .front_offset
+            .abs()
+            .partial_cmp(&(self.square_width / 2.0))
+            != Some(Ordering::Less)
+        {
+            return Err(ConfigError::OutOfRange("front_offset"));
+        }
+        Ok(())
+    }
+
+    pub fn pose_converter<const W: u8>(&self) -> PoseConverter<W> {
+        PoseConverter::new(
+            self.square_width,
+            self.wall.wall_width,
+            self.wall.ignore_radius_from_pillar,
+            self.wall.ignore_length_from_wall,
+        )
+    }
+
+    pub fn wall_detector<const W: u8>(&self) -> WallDetector<W> {
+        WallDetector::with_converter(self.pose_converter())
+    }
+
+    /// Returns the poses of the distance sensors of the robot at `pose`.
+    pub fn sensor_poses_at<'a>(&'a self, pose: &'a Pose) -> impl Iterator<Item = Pose> + 'a {
+        let (sin_th, cos_th) = (pose.theta.value.sin(), pose.theta.value.cos());
+        self.sensor_poses.iter().map(move |sensor| Pose {
+            x: pose.x + sensor.x * cos_th - sensor.y * sin_th,
+            y: pose.y + sensor.x * sin_th + sensor.y * cos_th,
+            theta: pose.theta + sensor.theta,
+        })
+    }
+
+    pub fn estimator(&self) -> Estimator {
+        Estimator::builder()
+            .period(self.period)
+            .alpha(self.estimator.alpha)
+            .slip_angle_const(self.estimator.slip_angle_const)
+            .approx_th(self.estimator.approx_th)
+            .build()
+    }
+
+    pub fn tracker(&self) -> Tracker {
+        Tracker::builder()
+            .period(self.period)
+            .xi_threshold(self.tracker.xi_threshold)
+            .zeta(self.tracker.zeta)
+            .b(self.tracker.b)
+            .build()
+    }
+
+    pub fn navigation_controller(&self) -> NavigationController {
+        NavigationController::builder()
+            .gain(self.navigation.gain)
+            .dgain(self.navigation.dgain)
+            .build()
+    }
+
+    pub fn supervisory_controller(&self) -> SupervisoryController {
+        SupervisoryController::builder()
+            .margin(self.supervisor.margin)
+            .avoidance_distance(self.supervisor.avoidance_distance)
+            .square_width(self.square_width)
+            .build()
+    }
+
+    pub fn controller(&self) -> Controller {
+        let config = &self.controller;
+        let builder = Controller::builder()
+            .trans_params(config.trans_params.clone())
+            .rot_params(config.rot_params.clone())
+            .period(self.period)
+            .max_voltage(config.max_voltage)
+            .max_trans_voltage(config.max_trans_voltage)
+            .max_rot_voltage(config.max_rot_voltage)
+            .anti_windup(config.anti_windup);
+        match &config.motor_model {
+            Some(model) => builder.motor_model(model.clone()).build(),
+            None => builder.build(),
+        }
+    }
+
+    pub fn straight_generator(&self) -> StraightGenerator {
+        let config = &self.straight;
+        StraightGenerator::with_profile(
+            config.v_max,
+            config.a_accel,
+            config.j_accel,
+            config.a_decel,
+            config.j_decel,
+            config.shape,
+            self.period,
+        )
+    }
+
+    pub fn spin_generator(&self) -> SpinGenerator {
+        SpinGenerator::new(
+            self.spin.max_angular_velocity,
+            self.spin.max_angular_acceleration,
+            self.spin.max_angular_jerk,
+            self.period,
+        )
+    }
+
+    pub fn pivot_generator(&self) -> PivotGenerator {
+        PivotGenerator::new(
+            self.spin.max_angular_velocity,
+            self.spin.max_angular_acceleration,
+            self.spin.max_angular_jerk,
+            self.wheel_interval,
+            self.max_wheel_velocity,
+            self.period,
+        )
+    }
+
+    pub fn slalom_generator(&self) -> SlalomGenerator {
+        SlalomGenerator::new(
+            self.period,
+            self.slalom.v_max,
+            self.slalom.a_max,
+            self.slalom.j_max,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uom::si::{
+        acceleration::meter_per_second_squared, angle::degree,
+        angular_acceleration::degree_per_second_squared, angular_jerk::degree_per_second_cubed,
+        angular_velocity::degree_per_second, electric_potential::volt, f32::Angle,
+        jerk::meter_per_second_cubed, length::millimeter, time::second, velocity::meter_per_second,
+    };
+
+    use super::*;
+    use crate::control::motor::MotorParameters;
+    use approx::assert_relative_eq;
+
+    fn config() -> RobotConfig {
+        let square_width = Length::new::<millimeter>(90.0);
+        let front_offset = Length::new::<millimeter>(10.0);
+        let mut sensor_poses = Vec::new();
+        for (x, y, theta) in [(23.0, 0.0, 0.0), (13.0, 11.5, 90.0), (13.0, -11.5, -90.0)] {
+            sensor_poses
+                .push(Pose {
+                    x: Length::new::<millimeter>(x),
+                    y: Length::new::<millimeter>(y),
+                    theta: Angle::new::<degree>(theta),
+                })
+                .unwrap();
+        }
+        RobotConfig {
+            period: Time::new::<second>(0.001),
+            square_width,
+            front_offset,
+            wheel_interval: Length::new::<millimeter>(33.5),
+            max_wheel_velocity: Velocity::new::<meter_per_second>(3.0),
+            sensor_poses,
+            wall: Default::default(),
+            estimator: Default::default(),
+            tracker: TrackerConfig {
+                xi_threshold: Velocity::new::<meter_per_second>(0.2),
+                zeta: 1.0,
+                b: 1.0,
+            },
+            navigation: NavigationConfig {
+                gain: 40.0,
+                dgain: 4.0,
+            },
+            supervisor: SupervisorConfig {
+                margin: 100.0,
+                avoidance_distance: Length::new::<millimeter>(20.0),
+            },
+            controller: ControllerConfig {
+                trans_params: ControlParameters {
+                    kp: 4.8497,
+                    ki: 29.5783,
+                    kd: 0.0,
+                    model_k: 1.865,
+                    model_t1: 0.4443,
+                },
+                rot_params: ControlParameters {
+                    kp: 0.21134,
+                    ki: 2.9317,
+                    kd: 0.0,
+                    model_k: 82.39,
+                    model_t1: 0.2855,
+                },
+                max_voltage: ElectricPotential::new::<volt>(3.7),
+                max_trans_voltage: ElectricPotential::new::<volt>(3.7),
+                max_rot_voltage: ElectricPotential::new::<volt>(f32::INFINITY),
+                anti_windup: AntiWindup::Clamping,
+                motor_model: None,
+            },
+            straight: StraightConfig {
+                v_max: Velocity::new::<meter_per_second>(1.0),
+                a_accel: Acceleration::new::<meter_per_second_squared>(10.0),
+                j_accel: Jerk::new::<meter_per_second_cubed>(100.0),
+                a_decel: Acceleration::new::<meter_per_second_squared>(8.0),
+                j_decel: Jerk::new::<meter_per_second_cubed>(100.0),
+                shape: ProfileShape::SCurve,
+            },
+            spin: SpinConfig {
+                max_angular_velocity: AngularVelocity::new::<degree_per_second>(1080.0),
+                max_angular_acceleration: AngularAcceleration::new::<degree_per_second_squared>(
+                    7200.0,
+                ),
+                max_angular_jerk: AngularJerk::new::<degree_per_second_cubed>(72000.0),
+            },
+            slalom: SlalomLimits {
+                v_max: Velocity::new::<meter_per_second>(1.0),
+                a_max: Acceleration::new::<meter_per_second_squared>(10.0),
+                j_max: Jerk::new::<meter_per_second_cubed>(100.0),
+            },
+            slalom_config: SlalomConfig::new(square_width, front_offset),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let config = config();
+        assert_eq!(config.validate(), Ok(()));
+        let bytes = postcard::to_allocvec(&config).unwrap();
+        let decoded: RobotConfig = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, config);
+
+        // every component can be built from the decoded configuration.
+        let _ = decoded.estimator();
+        let _ = decoded.tracker();
+        let _ = decoded.navigation_controller();
+        let _ = decoded.supervisory_controller();
+        let _ = decoded.controller();
+        let _ = decoded.straight_generator();
+        let _ = decoded.spin_generator();
+        let _ = decoded.pivot_generator();
+        let _ = decoded.slalom_generator();
+        let _ = decoded.wall_detector::<4>();
+    }
+
+    #[test]
+    fn test_sensor_poses_at() {
+        let config = config();
+        let pose = Pose {
+            x: Length::new::<millimeter>(45.0),
+            y: Length::new::<millimeter>(45.0),
+            theta: Angle::new::<degree>(90.0),
+        };
+        let poses = config.sensor_poses_at(&pose).collect::<std::vec::Vec<_>>();
+        assert_eq!(poses.len(), 3);
+        // the front sensor looks ahead and the left one looks to the left.
+        assert_relative_eq!(poses[0].x.get::<millimeter>(), 45.0, epsilon = 1e-3);
+        assert_relative_eq!(poses[0].y.get::<millimeter>(), 68.0, epsilon = 1e-3);
+        assert_relative_eq!(poses[1].x.get::<millimeter>(), 33.5, epsilon = 1e-3);
+        assert_relative_eq!(poses[1].theta.get::<degree>(), 180.0, epsilon = 1e-3);
+
+        // the right wall of the start square is seen by the right sensor.
+        let converter = config.pose_converter::<4>();
+        assert!(converter.convert(&poses[2]).is_some());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut invalid = config();
+        invalid.period = Time::new::<second>(0.0);
+        assert_eq!(invalid.validate(), Err(ConfigError::NotPositive("period")));
+
+        let mut invalid = config();
+        invalid.straight.a_decel = Acceleration::new::<meter_per_second_squared>(f32::NAN);
+        assert_eq!(
+            invalid.validate(),
+            Err(ConfigError::NotPositive("straight.a_decel"))
+        );
+
+        let mut invalid = config();
+        invalid.supervisor.margin = 0.0;
+        assert_eq!(
+            invalid.validate(),
+            Err(ConfigError::NotPositive("supervisor.margin"))
+        );
+
+        let mut invalid = config();
+        invalid.controller.max_rot_voltage = ElectricPotential::new::<volt>(0.0);
+        assert_eq!(
+            invalid.validate(),
+            Err(ConfigError::NotPositive("controller.max_rot_voltage"))
+        );
+
+        let mut invalid = config();
+        let motor = MotorParameters {
+            resistance: 1.07,
+            back_emf_constant: 0.000_207,
+            torque_constant: 0.000_207,
+            gear_ratio: 40.0 / 12.0,
+            wheel_radius: 0.0,
+            friction_torque: 0.0,
+        };
+        invalid.controller.motor_model = Some(MotorModel {
+            left: motor.clone(),
+            right: motor,
+            mass: 0.015,
+            inertia: 1.5e-6,
+            wheel_interval: 0.0335,
+        });
+        assert_eq!(
+            invalid.validate(),
+            Err(ConfigError::NotPositive(
+                "controller.motor_model.left.wheel_radius"
+            ))
+        );
+        if let Some(model) = invalid.controller.motor_model.as_mut() {
+            model.left.wheel_radius = 0.012;
+            model.right.back_emf_constant = 0.0;
+        }
+        assert_eq!(
+            invalid.validate(),
+            Err(ConfigError::NotPositive(
+                "controller.motor_model.right.back_emf_constant"
+            ))
+        );
+
+        let mut invalid = config();
+        invalid.slalom_config =
+            SlalomConfig::new(Length::new::<millimeter>(-90.0), Length::default());
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = config();
+        invalid.front_offset = Length::new::<millimeter>(50.0);
+        assert_eq!(
+            invalid.validate(),
+            Err(ConfigError::OutOfRange("front_offset"))
+        );
+    }
+}
@@ -1,3 +1,12 @@
+pub mod correction;
+// Fixtures shared by the tests of the controllers.
+#[cfg(test)]
+mod fixtures;
+pub mod identify;
+pub mod motor;
+pub mod mpc;
+pub mod pursuit;
+
 use core::marker::PhantomData;
 
 #[allow(unused_imports)]
@@ -6,16 +15,16 @@ use serde::{Deserialize, Serialize};
 use typed_builder::TypedBuilder;
 use uom::{
     si::{
+        acceleration::meter_per_second_squared,
         angle::radian,
         angular_velocity::radian_per_second,
         electric_potential::volt,
         f32::{
             Acceleration, Angle, AngularAcceleration, AngularJerk, AngularVelocity,
-            AvailableEnergy, ElectricPotential, Frequency, Jerk, Length, Ratio, Time, Velocity,
+            ElectricPotential, Frequency, Jerk, Length, Time, Velocity,
         },
         jerk::meter_per_second_cubed,
         length::millimeter,
-        ratio::ratio,
         time::second,
         Quantity, ISQ, SI,
     },
@@ -23,7 +32,12 @@ use uom::{
     Kind,
 };
 
-use crate::estimate::State;
+use crate::{
+    estimate::State,
+    solve::search::{Coordinate, WallState},
+    wall::Walls,
+};
+use motor::MotorModel;
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct MotorOutput {
@@ -153,6 +167,49 @@ impl Tracker {
     }
 }
 
+/// A tracker which follows time-indexed targets, so that trackers can be swapped.
+///
+/// [`pursuit::PathTracker`] follows a path regardless of the timing, and does not implement this.
+pub trait TargetTracker {
+    /// Returns the targets and the current values for [`Controller`].
+    ///
+    /// `targets` starts from the current target, and trackers which look ahead use the rest.
+    fn track_targets(
+        &mut self,
+        state: &State,
+        targets: &[Target],
+    ) -> (ControlTarget, ControlTarget);
+}
+
+/// [`Tracker`] with the input which `navigate` gives for the current target.
+pub struct NavigatedTracker<F> {
+    tracker: Tracker,
+    navigate: F,
+}
+
+impl<F> NavigatedTracker<F>
+where
+    F: FnMut(&State, &Target) -> TrackingInput,
+{
+    pub fn new(tracker: Tracker, navigate: F) -> Self {
+        Self { tracker, navigate }
+    }
+}
+
+impl<F> TargetTracker for NavigatedTracker<F>
+where
+    F: FnMut(&State, &Target) -> TrackingInput,
+{
+    fn track_targets(
+        &mut self,
+        state: &State,
+        targets: &[Target],
+    ) -> (ControlTarget, ControlTarget) {
+        let input = (self.navigate)(state, &targets[0]);
+        self.tracker.track(state, &targets[0], &input)
+    }
+}
+
 // normalize angle to [-pi, pi].
 fn normalize_angle(angle: Angle) -> Angle {
     use core::f32::consts::{PI, TAU};
@@ -182,6 +239,48 @@ pub struct ControlParameters {
     pub model_t1: f32,
 }
 
+// Limit `value` within `[-limit, limit]`.
+pub(crate) fn saturate(value: f32, limit: f32) -> f32 {
+    if value > limit {
+        limit
+    } else if value < -limit {
+        -limit
+    } else {
+        value
+    }
+}
+
+/// A method to keep the integrators of [`Controller`] from winding up on saturation.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AntiWindup {
+    /// The integrators always accumulate errors.
+    None,
+    /// The integrators stop while the output is saturated and the error would increase it.
+    Clamping,
+    /// The integrators are pulled back by the excess of the output
+    /// multiplied by the tracking gain in 1/s.
+    BackCalculation { tracking_gain: f32 },
+}
+
+impl Default for AntiWindup {
+    fn default() -> Self {
+        AntiWindup::None
+    }
+}
+
+/// Channels of [`Controller`] whose output was limited in the last control.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Saturation {
+    pub translational: bool,
+    pub rotational: bool,
+}
+
+impl Saturation {
+    pub fn is_saturated(&self) -> bool {
+        self.translational || self.rotational
+    }
+}
+
 #[derive(Debug, TypedBuilder)]
 pub struct Controller {
     trans_params: ControlParameters,
@@ -191,56 +290,180 @@ pub struct Controller {
     #[builder(default, setter(skip))]
     rot_sum: f32,
     period: Time,
+    /// The maximum absolute voltage of each motor.
+    #[builder(default = ElectricPotential::new::<volt>(f32::INFINITY))]
+    max_voltage: ElectricPotential,
+    /// The maximum absolute voltage of the translational channel.
+    #[builder(default = ElectricPotential::new::<volt>(f32::INFINITY))]
+    max_trans_voltage: ElectricPotential,
+    /// The maximum absolute voltage of the rotational channel.
+    #[builder(default = ElectricPotential::new::<volt>(f32::INFINITY))]
+    max_rot_voltage: ElectricPotential,
+    #[builder(default)]
+    anti_windup: AntiWindup,
+    /// The model which gives the feedforward voltages instead of the first-order models
+    /// in [`ControlParameters`].
+    ///
+    /// The feedback gains still come from [`ControlParameters`].
+    #[builder(default, setter(strip_option))]
+    motor_model: Option<MotorModel>,
+    #[builder(default = ElectricPotential::new::<volt>(f32::INFINITY), setter(skip))]
+    battery_voltage: ElectricPotential,
+    #[builder(default, setter(skip))]
+    saturation: Saturation,
 }
 
 impl Controller {
+    // Return the voltage and the error of a channel before saturation.
     fn control_each(
+        vol_f: f32,
         r: f32,
         dr: f32,
         y: f32,
         dy: f32,
         params: &ControlParameters,
-        period: Time,
-        error_sum: &mut f32,
-    ) -> ElectricPotential {
-        let vol_f = (dr * params.model_t1 + r) / params.model_k;
+        error_sum: f32,
+    ) -> (f32, f32) {
         let vol_p = params.kp * (r - y);
-        let vol_i = params.ki * *error_sum;
+        let vol_i = params.ki * error_sum;
         let vol_d = params.kd * (dr - dy);
-        *error_sum += (r - y) * period.get::<second>();
-        ElectricPotential::new::<volt>(vol_f + vol_p + vol_i + vol_d)
+        (vol_f + vol_p + vol_i + vol_d, r - y)
+    }
+
+    // Accumulate the error of a channel whose output `vol` is limited to `vol_sat`.
+    fn integrate(
+        period: Time,
+        anti_windup: AntiWindup,
+        error: f32,
+        vol: f32,
+        vol_sat: f32,
+        ki: f32,
+        error_sum: &mut f32,
+    ) {
+        let dt = period.get::<second>();
+        let saturated = vol != vol_sat;
+        match anti_windup {
+            AntiWindup::None => *error_sum += error * dt,
+            AntiWindup::Clamping => {
+                if !saturated || error * vol < 0.0 {
+                    *error_sum += error * dt;
+                }
+            }
+            AntiWindup::BackCalculation { tracking_gain } => {
+                let back = if ki == 0.0 {
+                    0.0
+                } else {
+                    tracking_gain * (vol_sat - vol) / ki
+                };
+                *error_sum += (error + back) * dt;
+            }
+        }
     }
 
     pub fn reset(&mut self) {
         self.trans_sum = Default::default();
         self.rot_sum = Default::default();
+        self.saturation = Default::default();
+    }
+
+    /// Sets the measured voltage of the battery, which also limits the output.
+    ///
+    /// The output is in volts regardless of the battery, so the caller should convert it
+    /// to duty ratios with the same measured voltage.
+    pub fn set_battery_voltage(&mut self, voltage: ElectricPotential) {
+        self.battery_voltage = voltage;
     }
 
+    // Return the feedforward voltages of the translational and rotational channels.
+    fn feedforward(&self, r: &ControlTarget) -> (f32, f32) {
+        match &self.motor_model {
+            Some(model) => {
+                let output = model.feedforward(r.v, r.a, r.omega, r.alpha);
+                let (left, right) = (output.left.get::<volt>(), output.right.get::<volt>());
+                ((right + left) / 2.0, (right - left) / 2.0)
+            }
+            None => {
+                let first_order = |r: f32, dr: f32, params: &ControlParameters| {
+                    (dr * params.model_t1 + r) / params.model_k
+                };
+                (
+                    first_order(r.v.value, r.a.value, &self.trans_params),
+                    first_order(r.omega.value, r.alpha.value, &self.rot_params),
+                )
+            }
+        }
+    }
+
+    /// Returns the channels saturated in the last control.
+    pub fn saturation(&self) -> Saturation {
+        self.saturation
+    }
+
+    /// Returns motor voltages within `max_voltage` and the battery voltage.
+    ///
+    /// Each channel is also limited by its own maximum voltage. The rotational channel takes
+    /// the voltage of the motors first and the translational one takes the rest.
     pub fn control(&mut self, r: &ControlTarget, y: &ControlTarget) -> MotorOutput {
-        let vol_t = Self::control_each(
+        let (vol_f_t, vol_f_r) = self.feedforward(r);
+        let (vol_t, error_t) = Self::control_each(
+            vol_f_t,
             r.v.value,
             r.a.value,
             y.v.value,
             y.a.value,
             &self.trans_params,
-            self.period,
-            &mut self.trans_sum,
+            self.trans_sum,
         );
-        let vol_r = Self::control_each(
+        let (vol_r, error_r) = Self::control_each(
+            vol_f_r,
             r.omega.value,
             r.alpha.value,
             y.omega.value,
             y.alpha.value,
             &self.rot_params,
+            self.rot_sum,
+        );
+
+        let max_voltage = if self.battery_voltage < self.max_voltage {
+            self.battery_voltage
+        } else {
+            self.max_voltage
+        }
+        .get::<volt>();
+        let vol_r_sat = saturate(vol_r, max_voltage.min(self.max_rot_voltage.get::<volt>()));
+        let vol_t_sat = saturate(
+            vol_t,
+            (max_voltage - vol_r_sat.abs()).min(self.max_trans_voltage.get::<volt>()),
+        );
+        self.saturation = Saturation {
+            translational: vol_t != vol_t_sat,
+            rotational: vol_r != vol_r_sat,
+        };
+
+        Self::integrate(
             self.period,
+            self.anti_windup,
+            error_t,
+            vol_t,
+            vol_t_sat,
+            self.trans_params.ki,
+            &mut self.trans_sum,
+        );
+        Self::integrate(
+            self.period,
+            self.anti_windup,
+            error_r,
+            vol_r,
+            vol_r_sat,
+            self.rot_params.ki,
             &mut self.rot_sum,
         );
-        let left_motor_voltage = vol_t - vol_r;
-        let right_motor_voltage = vol_t + vol_r;
 
+        let vol_t = ElectricPotential::new::<volt>(vol_t_sat);
+        let vol_r = ElectricPotential::new::<volt>(vol_r_sat);
         MotorOutput {
-            left: left_motor_voltage,
-            right: right_motor_voltage,
+            left: vol_t - vol_r,
+            right: vol_t + vol_r,
         }
     }
 }
@@ -295,31 +518,106 @@ pub struct SupervisoryController {
     square_width: Length,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SupervisionError {
+    /// No input satisfies all the barrier constraints.
+    Infeasible,
+    /// A barrier constraint has NaN coefficients, typically from a NaN state.
+    InvalidConstraint,
+}
+
+// A barrier constraint `a[0] * ux + a[1] * uy + b >= 0` in SI units.
+#[derive(Clone, Copy, Debug)]
+struct Constraint {
+    a: [f32; 2],
+    b: f32,
+}
+
+impl Constraint {
+    fn margin(&self, u: [f32; 2]) -> f32 {
+        self.a[0] * u[0] + self.a[1] * u[1] + self.b
+    }
+
+    fn is_satisfied(&self, u: [f32; 2]) -> bool {
+        const EPSILON: f32 = 1e-4;
+        let scale = (self.a[0] * u[0]).abs() + (self.a[1] * u[1]).abs() + self.b.abs();
+        self.margin(u) >= -EPSILON * scale
+    }
+
+    // Return the nearest point to `u` on the boundary.
+    fn project(&self, u: [f32; 2]) -> Option<[f32; 2]> {
+        let norm = self.a[0] * self.a[0] + self.a[1] * self.a[1];
+        if norm == 0.0 {
+            return None;
+        }
+        let k = self.margin(u) / norm;
+        Some([u[0] - k * self.a[0], u[1] - k * self.a[1]])
+    }
+
+    // Return the intersection of the boundaries.
+    fn intersect(&self, other: &Self) -> Option<[f32; 2]> {
+        let det = self.a[0] * other.a[1] - self.a[1] * other.a[0];
+        if det.abs() <= f32::EPSILON * (self.a[0].abs() + self.a[1].abs()) {
+            return None;
+        }
+        Some([
+            (-self.b * other.a[1] + other.b * self.a[1]) / det,
+            (-other.b * self.a[0] + self.b * other.a[0]) / det,
+        ])
+    }
+}
+
+const MAX_CONSTRAINTS: usize = 16;
+
 impl SupervisoryController {
-    pub fn supervise(&self, input: &TrackingInput, state: &State) -> TrackingInput {
-        let b_part = 2.0 * (state.x.v * state.x.v + state.y.v * state.y.v);
-        let (a1, a2, b) = self
+    /// Returns the nearest input to `input` which keeps the robot away from the pillars.
+    pub fn supervise(
+        &self,
+        input: &TrackingInput,
+        state: &State,
+    ) -> Result<TrackingInput, SupervisionError> {
+        let constraints = self
             .nearest_pillars(state)
-            .map(|(x, y)| {
-                let a1 = 2.0 * (state.x.x - x);
-                let a2 = 2.0 * (state.y.x - y);
-                let b = b_part
-                    + 4.0
-                        * self.margin
-                        * ((state.x.x - x) * state.x.v + (state.y.x - y) * state.y.v)
-                    + self.margin
-                        * self.margin
-                        * ((state.x.x - x) * (state.x.x - x) + (state.y.x - y) * (state.y.x - y)
-                            - self.avoidance_distance * self.avoidance_distance);
-                (a1, a2, b)
-            })
-            .min_by(|&(a11, a12, b1), &(a21, a22, b2)| {
-                (a11 * input.ux + a12 * input.uy + b1)
-                    .partial_cmp(&(a21 * input.ux + a22 * input.uy + b2))
-                    .expect("Should never fail: never be NaN.")
-            })
-            .unwrap();
-        self.apply_constraints(input, a1, a2, b)
+            .map(|pillar| self.pillar_constraint(state, pillar))
+            .collect::<heapless::Vec<_, MAX_CONSTRAINTS>>();
+        self.apply_constraints(input, &constraints)
+    }
+
+    /// Returns the nearest input to `input` which keeps the robot away from the pillars
+    /// and the walls which are known to exist.
+    pub fn supervise_with_walls<const W: u8>(
+        &self,
+        input: &TrackingInput,
+        state: &State,
+        walls: &Walls<W>,
+    ) -> Result<TrackingInput, SupervisionError> {
+        let mut constraints = self
+            .nearest_pillars(state)
+            .map(|pillar| self.pillar_constraint(state, pillar))
+            .collect::<heapless::Vec<_, MAX_CONSTRAINTS>>();
+        for constraint in self.wall_constraints(state, walls) {
+            constraints
+                .push(constraint)
+                .expect("Should never fail: the number of constraints is bounded.");
+        }
+        self.apply_constraints(input, &constraints)
+    }
+
+    // The barrier is `h = |p - c|^2 - d^2` and the constraint is `h'' + 2m h' + m^2 h >= 0`.
+    fn pillar_constraint(&self, state: &State, (x, y): (Length, Length)) -> Constraint {
+        let b_part = 2.0 * (state.x.v * state.x.v + state.y.v * state.y.v);
+        let a1 = 2.0 * (state.x.x - x);
+        let a2 = 2.0 * (state.y.x - y);
+        let b = b_part
+            + 4.0 * self.margin * ((state.x.x - x) * state.x.v + (state.y.x - y) * state.y.v)
+            + self.margin
+                * self.margin
+                * ((state.x.x - x) * (state.x.x - x) + (state.y.x - y) * (state.y.x - y)
+                    - self.avoidance_distance * self.avoidance_distance);
+        Constraint {
+            a: [a1.value, a2.value],
+            b: b.value,
+        }
     }
 
     fn nearest_pillars(&self, state: &State) -> impl Iterator<Item = (Length, Length)> + '_ {
@@ -340,63 +638,128 @@ impl SupervisoryController {
                 (false, false) => [(divx, divy - 1), (divx + 1, divy - 1)],
             },
         ))
-        .map(|(x, y)| {
-            (
-                (x + 1) as f32 * self.square_width,
-                (y + 1) as f32 * self.square_width,
-            )
-        })
+        .map(|(x, y)| (x as f32 * self.square_width, y as f32 * self.square_width))
+    }
+
+    // The barrier is the distance from the center line of the wall minus the avoidance distance.
+    // Only the walls in the row and the column of the current square are considered,
+    // since the pillars cover the rest.
+    fn wall_constraints<const W: u8>(
+        &self,
+        state: &State,
+        walls: &Walls<W>,
+    ) -> heapless::Vec<Constraint, 8> {
+        let (divx, _) = remquof(state.x.x, self.square_width);
+        let (divy, _) = remquof(state.y.x, self.square_width);
+        let exists = |vertical: bool, line: i8, cell: i8| {
+            if cell < 0 || cell >= W as i8 || line < 0 || line > W as i8 {
+                return false;
+            }
+            // The outer walls always exist.
+            if line == 0 || line == W as i8 {
+                return true;
+            }
+            let coord = if vertical {
+                Coordinate::<W>::new(((line - 1) as u8) << 1 | 1, (cell as u8) << 1)
+            } else {
+                Coordinate::<W>::new((cell as u8) << 1, ((line - 1) as u8) << 1 | 1)
+            };
+            coord.map_or(false, |coord| {
+                walls.wall_state(&coord) == WallState::Checked { exists: true }
+            })
+        };
+
+        let mut constraints = heapless::Vec::new();
+        for vertical in [true, false] {
+            let (p, v, div, cell) = if vertical {
+                (state.x.x, state.x.v, divx, divy)
+            } else {
+                (state.y.x, state.y.v, divy, divx)
+            };
+            for line in div - 1..=div + 2 {
+                if !exists(vertical, line, cell) {
+                    continue;
+                }
+                let offset = (p - line as f32 * self.square_width).value;
+                let n = if offset >= 0.0 { 1.0 } else { -1.0 };
+                let b = 2.0 * self.margin.value * n * v.value
+                    + self.margin.value
+                        * self.margin.value
+                        * (n * offset - self.avoidance_distance.value);
+                constraints
+                    .push(Constraint {
+                        a: if vertical { [n, 0.0] } else { [0.0, n] },
+                        b,
+                    })
+                    .expect("Should never fail: at most 8 walls are considered.");
+            }
+        }
+        constraints
+    }
+
+    // Solve the QP which minimizes the change of the acceleration under the constraints.
+    //
+    // The optimum of a QP in two dimensions has at most two active constraints,
+    // so the nearest feasible point among the projections and the intersections is the solution.
+    fn solve(u: [f32; 2], constraints: &[Constraint]) -> Result<[f32; 2], SupervisionError> {
+        let is_feasible = |u: [f32; 2]| constraints.iter().all(|c| c.is_satisfied(u));
+        if is_feasible(u) {
+            return Ok(u);
+        }
+        let distance = |v: [f32; 2]| (v[0] - u[0]) * (v[0] - u[0]) + (v[1] - u[1]) * (v[1] - u[1]);
+        let projections = constraints.iter().filter_map(|c| c.project(u));
+        let intersections = constraints.iter().enumerate().flat_map(|(i, c1)| {
+            constraints[i + 1..]
+                .iter()
+                .filter_map(move |c2| c1.intersect(c2))
+        });
+        projections
+            .chain(intersections)
+            .filter(|&v| is_feasible(v))
+            .min_by(|&v1, &v2| {
+                distance(v1)
+                    .partial_cmp(&distance(v2))
+                    .expect("Should never fail: never be NaN.")
+            })
+            .ok_or(SupervisionError::Infeasible)
     }
 
     fn apply_constraints(
         &self,
         &TrackingInput { ux, uy, dux, duy }: &TrackingInput,
-        a1: Length,
-        a2: Length,
-        b: AvailableEnergy,
-    ) -> TrackingInput {
-        assert!(!a1.is_nan());
-        assert!(!a2.is_nan());
-        assert!(!b.is_nan());
-        if a1 * ux + a2 * uy + b >= AvailableEnergy::default() {
-            return TrackingInput { ux, uy, dux, duy };
+        constraints: &[Constraint],
+    ) -> Result<TrackingInput, SupervisionError> {
+        if constraints
+            .iter()
+            .any(|c| c.a[0].is_nan() || c.a[1].is_nan() || c.b.is_nan())
+        {
+            return Err(SupervisionError::InvalidConstraint);
         }
-        let (ux2, uy2) = if a2 != Length::default() {
-            let a = -a1 / a2;
-            let b = -b / a2;
-            let c1 = a * a + Ratio::new::<ratio>(1.0);
-            let c2 = 2.0 * (a * b - ux - a * uy);
-            let ux2 = -c2 / (2.0 * c1);
-            let uy2 = a * ux2 + b;
-            (ux2, uy2)
-        } else if a1 != Length::default() {
-            let a = -a2 / a1;
-            let b = -b / a1;
-            let c1 = a * a + Ratio::new::<ratio>(1.0);
-            let c2 = 2.0 * (a * b - uy - a * ux);
-            let uy2 = -c2 / (2.0 * c1);
-            let ux2 = a * uy2 + b;
-            (ux2, uy2)
-        } else {
-            unreachable!("Infeasible")
-        };
-        assert!(!ux2.is_nan());
-        assert!(!uy2.is_nan());
+        let [ux2, uy2] = Self::solve([ux.value, uy.value], constraints)?;
+        if ux2 == ux.value && uy2 == uy.value {
+            return Ok(TrackingInput { ux, uy, dux, duy });
+        }
+        let ux2 = Acceleration::new::<meter_per_second_squared>(ux2);
+        let uy2 = Acceleration::new::<meter_per_second_squared>(uy2);
 
         let to_polar = |x: f32, y: f32| ((x * x + y * y).sqrt(), y.atan2(x));
         let (r, t) = to_polar(ux.value, uy.value);
         let (r2, t2) = to_polar(ux2.value, uy2.value);
         let (dr, dt) = to_polar(dux.value, duy.value);
-        let dr2 = Jerk::new::<meter_per_second_cubed>(dr * r2 / r);
-        let dt2 = dt + t2 - t;
-        let dux2 = dr2 * dt2.cos();
-        let duy2 = dr2 * dt2.sin();
-        TrackingInput {
+        // The jerk keeps its direction relative to the acceleration and scales with it.
+        let (dux2, duy2) = if r > 0.0 {
+            let dr2 = Jerk::new::<meter_per_second_cubed>(dr * r2 / r);
+            let dt2 = dt + t2 - t;
+            (dr2 * dt2.cos(), dr2 * dt2.sin())
+        } else {
+            (dux, duy)
+        };
+        Ok(TrackingInput {
             ux: ux2,
             uy: uy2,
             dux: dux2,
             duy: duy2,
-        }
+        })
     }
 }
 
@@ -404,6 +767,8 @@ impl SupervisoryController {
 mod tests {
     use super::*;
     use approx::assert_relative_eq;
+    use fixtures::{maze4, state, supervisor, tracking_input};
+    use uom::si::velocity::meter_per_second;
 
     #[test]
     fn test_normalize_angle() {
@@ -438,4 +803,203 @@ mod tests {
             assert_relative_eq!(rem.get::<millimeter>(), exp_rem, epsilon = 0.001);
         }
     }
+
+    fn controller(max_voltage: f32, anti_windup: AntiWindup) -> Controller {
+        let params = ControlParameters {
+            kp: 1.0,
+            ki: 10.0,
+            kd: 0.0,
+            model_k: 1.0,
+            model_t1: 0.1,
+        };
+        Controller::builder()
+            .trans_params(params.clone())
+            .rot_params(params)
+            .period(Time::new::<second>(0.001))
+            .max_voltage(ElectricPotential::new::<volt>(max_voltage))
+            .anti_windup(anti_windup)
+            .build()
+    }
+
+    fn control_target(v: f32, omega: f32) -> ControlTarget {
+        ControlTarget {
+            v: Velocity::new::<meter_per_second>(v),
+            a: Default::default(),
+            omega: AngularVelocity::new::<radian_per_second>(omega),
+            alpha: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_controller_saturation() {
+        let mut controller = controller(3.0, AntiWindup::None);
+        let output = controller.control(&control_target(1.5, 0.5), &control_target(0.0, 0.0));
+        assert_relative_eq!(output.right.get::<volt>(), 3.0);
+        assert_relative_eq!(output.left.get::<volt>(), 1.0);
+        assert_eq!(
+            controller.saturation(),
+            Saturation {
+                translational: true,
+                rotational: false,
+            }
+        );
+
+        // the rotation takes priority.
+        let output = controller.control(&control_target(1.0, 2.0), &control_target(0.0, 0.0));
+        assert_relative_eq!(output.right.get::<volt>(), 3.0);
+        assert_relative_eq!(output.left.get::<volt>(), -3.0);
+        assert!(controller.saturation().rotational);
+    }
+
+    #[test]
+    fn test_channel_voltage_limits() {
+        let params = ControlParameters {
+            kp: 1.0,
+            ki: 0.0,
+            kd: 0.0,
+            model_k: 1.0,
+            model_t1: 0.1,
+        };
+        let mut controller = Controller::builder()
+            .trans_params(params.clone())
+            .rot_params(params)
+            .period(Time::new::<second>(0.001))
+            .max_voltage(ElectricPotential::new::<volt>(3.0))
+            .max_trans_voltage(ElectricPotential::new::<volt>(2.0))
+            .max_rot_voltage(ElectricPotential::new::<volt>(0.5))
+            .build();
+
+        // each channel is limited below the voltage left for it by the motors.
+        let output = controller.control(&control_target(1.5, 1.0), &control_target(0.0, 0.0));
+        assert_relative_eq!(output.right.get::<volt>(), 2.5);
+        assert_relative_eq!(output.left.get::<volt>(), 1.5);
+        assert_eq!(
+            controller.saturation(),
+            Saturation {
+                translational: true,
+                rotational: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_battery_voltage_limit() {
+        let mut controller = controller(3.0, AntiWindup::None);
+        controller.set_battery_voltage(ElectricPotential::new::<volt>(2.0));
+        let output = controller.control(&control_target(1.5, 0.5), &control_target(0.0, 0.0));
+        assert_relative_eq!(output.right.get::<volt>(), 2.0);
+        assert_relative_eq!(output.left.get::<volt>(), 0.0);
+    }
+
+    #[test]
+    fn test_anti_windup() {
+        // the integral term after a long saturation with an error, such as a wall bump.
+        let windup = |anti_windup| {
+            let mut controller = controller(3.0, anti_windup);
+            for _ in 0..2000 {
+                controller.control(&control_target(1.0, 0.0), &control_target(0.0, 0.0));
+            }
+            assert!(controller.saturation().translational);
+            let output = controller.control(&control_target(1.0, 0.0), &control_target(1.0, 0.0));
+            output.right.get::<volt>()
+        };
+        let none = windup(AntiWindup::None);
+        let clamping = windup(AntiWindup::Clamping);
+        let back_calculation = windup(AntiWindup::BackCalculation {
+            tracking_gain: 10.0,
+        });
+        assert_relative_eq!(none, 3.0);
+        assert!(clamping < none);
+        assert!(back_calculation < none);
+        assert!(clamping <= 3.0 && back_calculation <= 3.0);
+    }
+
+    #[test]
+    fn test_supervise_walls() {
+        let walls = maze4();
+        let supervisor = supervisor();
+
+        // the square (0, 0) has the wall to the east.
+        let east = state(60.0, 45.0, 0.0, 1.0);
+        let input = tracking_input(10.0, 0.0);
+        let with_walls = supervisor
+            .supervise_with_walls(&input, &east, &walls)
+            .unwrap();
+        let without_walls = supervisor.supervise(&input, &east).unwrap();
+        assert!(with_walls.ux.value <= -100.0 + 1e-2);
+        assert!(without_walls.ux > with_walls.ux);
+
+        // both outer walls are active in the corner.
+        let corner = state(30.0, 30.0, -135.0, core::f32::consts::SQRT_2);
+        let input = supervisor
+            .supervise_with_walls(&tracking_input(0.0, 0.0), &corner, &walls)
+            .unwrap();
+        assert_relative_eq!(input.ux.value, 100.0, epsilon = 1e-2);
+        assert_relative_eq!(input.uy.value, 100.0, epsilon = 1e-2);
+    }
+
+    #[test]
+    fn test_nearest_pillars() {
+        let supervisor = supervisor();
+
+        // the pillars are at the corners of the square (1, 0) and the two below it.
+        let state = state(135.0, 20.0, 0.0, 0.0);
+        let pillars = supervisor
+            .nearest_pillars(&state)
+            .map(|(x, y)| (x.get::<millimeter>(), y.get::<millimeter>()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pillars,
+            vec![
+                (90.0, 0.0),
+                (180.0, 0.0),
+                (90.0, 90.0),
+                (180.0, 90.0),
+                (90.0, -90.0),
+                (180.0, -90.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_supervise_infeasible() {
+        let constraints = [
+            Constraint {
+                a: [1.0, 0.0],
+                b: -1.0,
+            },
+            Constraint {
+                a: [-1.0, 0.0],
+                b: -1.0,
+            },
+        ];
+        assert_eq!(
+            SupervisoryController::solve([0.0, 0.0], &constraints),
+            Err(SupervisionError::Infeasible)
+        );
+        assert_eq!(
+            SupervisoryController::solve([0.0, 0.0], &constraints[..1]),
+            Ok([1.0, 0.0])
+        );
+    }
+
+    #[test]
+    fn test_supervise_invalid() {
+        let supervisor = supervisor();
+        let input = tracking_input(10.0, 0.0);
+        let constraints = [Constraint {
+            a: [1.0, f32::NAN],
+            b: 0.0,
+        }];
+        assert_eq!(
+            supervisor.apply_constraints(&input, &constraints),
+            Err(SupervisionError::InvalidConstraint)
+        );
+        // a NaN state near a pillar is rejected instead of panicking.
+        let state = state(40.0, 40.0, 45.0, f32::NAN);
+        assert_eq!(
+            supervisor.supervise(&input, &state),
+            Err(SupervisionError::InvalidConstraint)
+        );
+    }
 }
@@ -0,0 +1,393 @@
+use core::marker::PhantomData;
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+use typed_builder::TypedBuilder;
+use uom::si::{
+    angle::radian,
+    angular_velocity::radian_per_second,
+    f32::{Angle, AngularVelocity, Frequency, Length, Time, Velocity},
+    velocity::meter_per_second,
+};
+
+use super::{saturate, ControlTarget, GainType, TrackingInput};
+use crate::{
+    estimate::State,
+    solve::search::WallState,
+    wall::{Pose, PoseConverter, WallDetector, Walls},
+};
+
+/// A reading of a distance sensor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SideReading {
+    /// The pose of the sensor relative to the center of the robot.
+    pub pose: Pose,
+    pub distance: Length,
+}
+
+/// The lateral offset of the robot estimated from the side walls.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SideOffset {
+    /// The offset to the left of the robot from the estimated position.
+    pub lateral: Length,
+    /// How much the offset is trusted in [0, 1].
+    pub weight: f32,
+}
+
+/// Corrects the lateral position during straights with the side walls.
+///
+/// The offset is fed into [`TrackingInput`] as a lateral acceleration,
+/// which the tracker turns into a correction of the angular velocity.
+///
+/// The heading is not estimated, since each side sensor sees a single point of a wall
+/// perpendicular to the robot. The derivative term damps the heading error instead,
+/// which appears as the lateral velocity.
+#[derive(Debug, TypedBuilder)]
+pub struct WallCorrector<const W: u8> {
+    period: Time,
+    square_width: Length,
+    #[builder(setter(transform = |value: f32| GainType{ value, dimension: PhantomData, units: PhantomData }))]
+    gain: GainType,
+    #[builder(setter(transform = |value: f32| Frequency{ value, dimension: PhantomData, units: PhantomData }))]
+    dgain: Frequency,
+    /// The length along a wall from a pillar over which the correction fades in.
+    fade_length: Length,
+    /// Readings farther than this from the expected distance are ignored.
+    max_error: Length,
+    #[builder(default, setter(skip))]
+    converter: PoseConverter<W>,
+    #[builder(default, setter(skip))]
+    last_lateral: Option<Length>,
+}
+
+impl<const W: u8> WallCorrector<W> {
+    /// Returns the offset estimated from the readings of the walls which are known to exist.
+    pub fn offset(
+        &self,
+        state: &State,
+        walls: &Walls<W>,
+        readings: &[SideReading],
+    ) -> Option<SideOffset> {
+        let sin_th = state.theta.x.value.sin();
+        let cos_th = state.theta.x.value.cos();
+        let mut sum = Length::default();
+        let mut weight_sum = 0.0;
+        let mut weight_max = 0.0f32;
+        for reading in readings {
+            let pose = Pose {
+                x: state.x.x + reading.pose.x * cos_th - reading.pose.y * sin_th,
+                y: state.y.x + reading.pose.x * sin_th + reading.pose.y * cos_th,
+                theta: state.theta.x + reading.pose.theta,
+            };
+            let info = if let Some(info) = self.converter.convert(&pose) {
+                info
+            } else {
+                continue;
+            };
+            if walls.wall_state(&info.coord) != (WallState::Checked { exists: true }) {
+                continue;
+            }
+            let error = reading.distance - info.existing_distance;
+            if error.abs() > self.max_error {
+                continue;
+            }
+            let sin_phi = pose.theta.value.sin();
+            let cos_phi = pose.theta.value.cos();
+            // The walls with odd x are parallel to the y axis.
+            let (lateral, along) = if info.coord.x() & 1 == 1 {
+                (
+                    error * cos_phi * sin_th,
+                    pose.y + info.existing_distance * sin_phi,
+                )
+            } else {
+                (
+                    -error * sin_phi * cos_th,
+                    pose.x + info.existing_distance * cos_phi,
+                )
+            };
+            let rem = (along / self.square_width).value.rem_euclid(1.0) * self.square_width;
+            let to_pillar = rem.min(self.square_width - rem);
+            let weight = (to_pillar / self.fade_length).value.min(1.0);
+            sum += lateral * weight;
+            weight_sum += weight;
+            weight_max = weight_max.max(weight);
+        }
+        if weight_sum > 0.0 {
+            Some(SideOffset {
+                lateral: sum / weight_sum,
+                weight: weight_max,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns `input` with the correction of the lateral offset.
+    pub fn correct(
+        &mut self,
+        input: &TrackingInput,
+        state: &State,
+        walls: &Walls<W>,
+        readings: &[SideReading],
+    ) -> TrackingInput {
+        let offset = if let Some(offset) = self.offset(state, walls, readings) {
+            offset
+        } else {
+            self.last_lateral = None;
+            return *input;
+        };
+        let velocity = self
+            .last_lateral
+            .map(|last| (offset.lateral - last) / self.period)
+            .unwrap_or_default();
+        self.last_lateral = Some(offset.lateral);
+
+        // The normal vector to the left of the robot.
+        let nx = -state.theta.x.value.sin();
+        let ny = state.theta.x.value.cos();
+        let u = -offset.weight * (self.gain * offset.lateral + self.dgain * velocity);
+        let du = -offset.weight * self.gain * velocity;
+        TrackingInput {
+            ux: input.ux + u * nx,
+            uy: input.uy + u * ny,
+            dux: input.dux + du * nx,
+            duy: input.duy + du * ny,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.last_lateral = None;
+    }
+}
+
+/// The result of a step of [`FrontAligner`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum Alignment {
+    /// The robot should follow the target to the front wall.
+    Aligning(ControlTarget),
+    /// The robot reached the target distance and yaw.
+    Aligned,
+    /// No front wall is known to exist, so the robot should keep its estimated pose.
+    NoFrontWall,
+    /// The robot did not converge in time.
+    TimedOut,
+}
+
+/// Servos the robot to a distance and a yaw against the front wall with two front sensors.
+#[derive(Debug, TypedBuilder)]
+pub struct FrontAligner<const W: u8> {
+    period: Time,
+    /// The target mean distance of the front sensors.
+    target_distance: Length,
+    /// The lateral interval between the two front sensors.
+    sensor_interval: Length,
+    #[builder(setter(transform = |value: f32| Frequency{ value, dimension: PhantomData, units: PhantomData }))]
+    gain: Frequency,
+    #[builder(setter(transform = |value: f32| Frequency{ value, dimension: PhantomData, units: PhantomData }))]
+    rot_gain: Frequency,
+    max_velocity: Velocity,
+    max_angular_velocity: AngularVelocity,
+    distance_tolerance: Length,
+    angle_tolerance: Angle,
+    timeout: Time,
+    #[builder(default, setter(skip))]
+    elapsed: Time,
+}
+
+impl<const W: u8> FrontAligner<W> {
+    /// Returns the next step of the alignment from the readings of the left and right front sensors.
+    ///
+    /// `pose` is the estimated pose of the robot, which decides the front wall in `detector`.
+    pub fn align(
+        &mut self,
+        pose: &Pose,
+        detector: &WallDetector<W>,
+        left: Length,
+        right: Length,
+    ) -> Alignment {
+        if detector.wall_exists(pose) != Some(true) {
+            return Alignment::NoFrontWall;
+        }
+        if self.elapsed >= self.timeout {
+            return Alignment::TimedOut;
+        }
+        self.elapsed += self.period;
+
+        let error = (left + right) / 2.0 - self.target_distance;
+        // The yaw of the robot against the wall, which is counterclockwise when the left sensor
+        // is farther than the right one.
+        let yaw = Angle::new::<radian>(((left - right) / self.sensor_interval).value.atan());
+        if error.abs() < self.distance_tolerance && yaw.abs() < self.angle_tolerance {
+            return Alignment::Aligned;
+        }
+
+        let v = Velocity::new::<meter_per_second>(saturate(
+            Velocity::from(self.gain * error).value,
+            self.max_velocity.value,
+        ));
+        let omega = AngularVelocity::new::<radian_per_second>(saturate(
+            AngularVelocity::from(-self.rot_gain * yaw).value,
+            self.max_angular_velocity.value,
+        ));
+        Alignment::Aligning(ControlTarget {
+            v,
+            a: Default::default(),
+            omega,
+            alpha: Default::default(),
+        })
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = Default::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use uom::si::{angle::degree, length::millimeter};
+
+    use super::*;
+    use crate::control::fixtures::{front_aligner, maze4, state, wall_corrector};
+
+    fn readings(left: f32, right: f32) -> [SideReading; 2] {
+        [
+            SideReading {
+                pose: Pose {
+                    x: Length::new::<millimeter>(13.0),
+                    y: Length::new::<millimeter>(11.5),
+                    theta: Angle::new::<degree>(90.0),
+                },
+                distance: Length::new::<millimeter>(left),
+            },
+            SideReading {
+                pose: Pose {
+                    x: Length::new::<millimeter>(13.0),
+                    y: Length::new::<millimeter>(-11.5),
+                    theta: Angle::new::<degree>(-90.0),
+                },
+                distance: Length::new::<millimeter>(right),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_side_offset() {
+        let walls = maze4();
+        let corrector = wall_corrector();
+        // the robot is 2mm to the right of the estimated position at the center of (1, 1).
+        let offset = corrector
+            .offset(
+                &state(135.0, 135.0, 90.0, 0.0),
+                &walls,
+                &readings(32.5, 28.5),
+            )
+            .unwrap();
+        assert_relative_eq!(offset.lateral.get::<millimeter>(), -2.0, epsilon = 1e-3);
+        assert_relative_eq!(offset.weight, 1.0);
+
+        // the correction fades out near the pillars.
+        let offset = corrector
+            .offset(
+                &state(135.0, 155.0, 90.0, 0.0),
+                &walls,
+                &readings(32.5, 28.5),
+            )
+            .unwrap();
+        assert_relative_eq!(offset.lateral.get::<millimeter>(), -2.0, epsilon = 1e-3);
+        assert_relative_eq!(offset.weight, 0.6, epsilon = 1e-3);
+        assert!(corrector
+            .offset(
+                &state(135.0, 160.0, 90.0, 0.0),
+                &walls,
+                &readings(32.5, 28.5)
+            )
+            .is_none());
+
+        // a reading far from the expected distance, such as a missing wall, is ignored.
+        let offset = corrector
+            .offset(
+                &state(135.0, 135.0, 90.0, 0.0),
+                &walls,
+                &readings(32.5, 100.0),
+            )
+            .unwrap();
+        assert_relative_eq!(offset.lateral.get::<millimeter>(), -2.0, epsilon = 1e-3);
+        assert!(corrector
+            .offset(
+                &state(135.0, 135.0, 90.0, 0.0),
+                &walls,
+                &readings(100.0, 100.0)
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn test_correct() {
+        let walls = maze4();
+        let mut corrector = wall_corrector();
+        let input = corrector.correct(
+            &TrackingInput {
+                ux: Default::default(),
+                uy: Default::default(),
+                dux: Default::default(),
+                duy: Default::default(),
+            },
+            &state(135.0, 135.0, 90.0, 0.0),
+            &walls,
+            &readings(32.5, 28.5),
+        );
+        // the robot heading north is pulled to the west.
+        assert!(input.ux.value < 0.0);
+        assert_relative_eq!(input.uy.value, 0.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_front_alignment() {
+        let walls = maze4();
+        let detector = WallDetector::with_walls(&walls);
+        // the cell (2, 1) has the front wall to the south and no wall to the north.
+        let south = Pose {
+            x: Length::new::<millimeter>(225.0),
+            y: Length::new::<millimeter>(135.0),
+            theta: Angle::new::<degree>(-90.0),
+        };
+        let north = Pose {
+            theta: Angle::new::<degree>(90.0),
+            ..south
+        };
+        let mut aligner = front_aligner();
+        let mm = Length::new::<millimeter>;
+
+        assert_eq!(
+            aligner.align(&north, &detector, mm(60.0), mm(60.0)),
+            Alignment::NoFrontWall
+        );
+        assert_eq!(
+            aligner.align(&south, &detector, mm(60.3), mm(60.1)),
+            Alignment::Aligned
+        );
+
+        // the robot is too far and turned counterclockwise.
+        let target = match aligner.align(&south, &detector, mm(70.0), mm(66.0)) {
+            Alignment::Aligning(target) => target,
+            alignment => panic!("{:?}", alignment),
+        };
+        assert_relative_eq!(target.v.get::<meter_per_second>(), 0.08, epsilon = 1e-5);
+        assert!(target.omega.value < 0.0);
+
+        // the velocity is limited.
+        let target = match aligner.align(&south, &detector, mm(100.0), mm(100.0)) {
+            Alignment::Aligning(target) => target,
+            alignment => panic!("{:?}", alignment),
+        };
+        assert_relative_eq!(target.v.get::<meter_per_second>(), 0.2);
+
+        while aligner.align(&south, &detector, mm(70.0), mm(70.0)) != Alignment::TimedOut {}
+        aligner.reset();
+        assert_ne!(
+            aligner.align(&south, &detector, mm(70.0), mm(70.0)),
+            Alignment::TimedOut
+        );
+    }
+}
@@ -0,0 +1,145 @@
+#[allow(unused_imports)]
+use micromath::F32Ext;
+use uom::si::{
+    acceleration::meter_per_second_squared,
+    angle::degree,
+    angular_velocity::degree_per_second,
+    electric_potential::volt,
+    f32::{Acceleration, Angle, AngularVelocity, ElectricPotential, Length, Time, Velocity},
+    length::{meter, millimeter},
+    time::second,
+    velocity::meter_per_second,
+};
+
+use super::{
+    correction::{FrontAligner, WallCorrector},
+    identify::FirstOrderModel,
+    mpc::MpcTracker,
+    pursuit::{PathTracker, PathTrackingLaw},
+    AngleTarget, LengthTarget, SupervisoryController, Target, TrackingInput,
+};
+use crate::{
+    estimate::{AngleState, LengthState, State},
+    wall::Walls,
+};
+
+pub fn period() -> Time {
+    Time::new::<second>(0.001)
+}
+
+pub fn maze4() -> Walls<4> {
+    include_str!("../../mazes/maze4_1.dat")
+        .parse::<Walls<4>>()
+        .unwrap()
+}
+
+/// Returns a state at (`x`, `y`) in mm heading `theta` in degrees at `v` in m/s.
+pub fn state(x: f32, y: f32, theta: f32, v: f32) -> State {
+    let theta = Angle::new::<degree>(theta);
+    let (sin, cos) = (theta.value.sin(), theta.value.cos());
+    State {
+        x: LengthState {
+            x: Length::new::<millimeter>(x),
+            v: Velocity::new::<meter_per_second>(v * cos),
+            ..Default::default()
+        },
+        y: LengthState {
+            x: Length::new::<millimeter>(y),
+            v: Velocity::new::<meter_per_second>(v * sin),
+            ..Default::default()
+        },
+        theta: AngleState {
+            x: theta,
+            ..Default::default()
+        },
+    }
+}
+
+/// Returns targets which go straight from the origin heading `theta` in degrees
+/// from `v` in m/s with `a` in m/s^2.
+pub fn straight_path(theta: f32, v: f32, a: f32) -> Vec<Target> {
+    let theta = Angle::new::<degree>(theta);
+    let (sin, cos) = (theta.value.sin(), theta.value.cos());
+    (0..500)
+        .map(|i| {
+            let t = i as f32 * period().value;
+            let (x, v) = (v * t + a * t * t / 2.0, v + a * t);
+            let length = |ratio: f32| LengthTarget {
+                x: Length::new::<meter>(x * ratio),
+                v: Velocity::new::<meter_per_second>(v * ratio),
+                a: Acceleration::new::<meter_per_second_squared>(a * ratio),
+                ..Default::default()
+            };
+            Target {
+                x: length(cos),
+                y: length(sin),
+                theta: AngleTarget {
+                    x: theta,
+                    ..Default::default()
+                },
+            }
+        })
+        .collect()
+}
+
+pub fn wall_corrector() -> WallCorrector<4> {
+    WallCorrector::builder()
+        .period(period())
+        .square_width(Length::new::<millimeter>(90.0))
+        .gain(100.0)
+        .dgain(10.0)
+        .fade_length(Length::new::<millimeter>(20.0))
+        .max_error(Length::new::<millimeter>(10.0))
+        .build()
+}
+
+pub fn front_aligner() -> FrontAligner<4> {
+    FrontAligner::builder()
+        .period(period())
+        .target_distance(Length::new::<millimeter>(60.0))
+        .sensor_interval(Length::new::<millimeter>(20.0))
+        .gain(10.0)
+        .rot_gain(20.0)
+        .max_velocity(Velocity::new::<meter_per_second>(0.2))
+        .max_angular_velocity(AngularVelocity::new::<degree_per_second>(180.0))
+        .distance_tolerance(Length::new::<millimeter>(1.0))
+        .angle_tolerance(Angle::new::<degree>(1.0))
+        .timeout(Time::new::<second>(0.5))
+        .build()
+}
+
+pub fn tracking_input(ux: f32, uy: f32) -> TrackingInput {
+    TrackingInput {
+        ux: Acceleration::new::<meter_per_second_squared>(ux),
+        uy: Acceleration::new::<meter_per_second_squared>(uy),
+        dux: Default::default(),
+        duy: Default::default(),
+    }
+}
+
+pub fn supervisor() -> SupervisoryController {
+    SupervisoryController::builder()
+        .margin(100.0)
+        .avoidance_distance(Length::new::<millimeter>(20.0))
+        .square_width(Length::new::<millimeter>(90.0))
+        .build()
+}
+
+pub fn mpc_tracker() -> MpcTracker<10> {
+    MpcTracker::builder()
+        .period(period())
+        .trans_model(FirstOrderModel {
+            k: 1.865,
+            t1: Time::new::<second>(0.4443),
+        })
+        .rot_model(FirstOrderModel {
+            k: 82.39,
+            t1: Time::new::<second>(0.2855),
+        })
+        .max_voltage(ElectricPotential::new::<volt>(3.7))
+        .build()
+}
+
+pub fn path_tracker(law: PathTrackingLaw) -> PathTracker {
+    PathTracker::builder().period(period()).law(law).build()
+}
@@ -0,0 +1,363 @@
+use core::f32::consts::PI;
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+use uom::si::{
+    electric_potential::volt,
+    f32::{ElectricPotential, Frequency, Time},
+    frequency::hertz,
+    time::second,
+};
+
+use crate::{
+    control::{ControlParameters, MotorOutput},
+    estimate::SensorValue,
+};
+
+/// A voltage sequence which excites the robot for the identification.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Excitation {
+    /// A constant voltage from the start.
+    Step { voltage: ElectricPotential },
+    /// A sinusoidal voltage whose frequency sweeps linearly from `start` to `end` in `duration`.
+    Chirp {
+        amplitude: ElectricPotential,
+        start: Frequency,
+        end: Frequency,
+        duration: Time,
+    },
+}
+
+impl Excitation {
+    /// Returns the voltage at `t` from the start of the excitation.
+    pub fn voltage(&self, t: Time) -> ElectricPotential {
+        match *self {
+            Excitation::Step { voltage } => voltage,
+            Excitation::Chirp {
+                amplitude,
+                start,
+                end,
+                duration,
+            } => {
+                let t = t.get::<second>();
+                let f0 = start.get::<hertz>();
+                let rate = (end.get::<hertz>() - f0) / duration.get::<second>();
+                amplitude * (2.0 * PI * (f0 * t + rate * t * t / 2.0)).sin()
+            }
+        }
+    }
+
+    /// Returns the motor voltages which excite only the translational channel.
+    pub fn translational(&self, t: Time) -> MotorOutput {
+        let voltage = self.voltage(t);
+        MotorOutput {
+            left: voltage,
+            right: voltage,
+        }
+    }
+
+    /// Returns the motor voltages which excite only the rotational channel.
+    pub fn rotational(&self, t: Time) -> MotorOutput {
+        let voltage = self.voltage(t);
+        MotorOutput {
+            left: -voltage,
+            right: voltage,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IdentificationError {
+    /// The input did not excite the channel enough to separate the parameters.
+    InsufficientExcitation,
+    /// The fitted model is not a stable first-order lag.
+    Unstable,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TuningError {
+    /// The poles are slower than the plant, which needs a negative proportional gain.
+    InsufficientBandwidth,
+    /// The time constant of the closed loop is not positive.
+    InvalidTimeConstant,
+}
+
+/// A rule to choose the gains of the controller from a model.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum TuningRule {
+    /// Places the poles of the error dynamics at the natural frequency in rad/s
+    /// with the damping ratio.
+    ///
+    /// `2 * damping_ratio * natural_frequency` must be at least `1 / t1` of the model.
+    PolePlacement {
+        natural_frequency: f32,
+        damping_ratio: f32,
+    },
+    /// Internal model control, which cancels the plant pole and leaves a first-order lag
+    /// with the time constant.
+    Imc { time_constant: Time },
+}
+
+/// A first-order model `k / (t1 s + 1)` from voltage to velocity.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct FirstOrderModel {
+    pub k: f32,
+    pub t1: Time,
+}
+
+impl FirstOrderModel {
+    /// Returns the parameters of the controller with this model and the given gains.
+    pub fn with_gains(&self, kp: f32, ki: f32, kd: f32) -> ControlParameters {
+        ControlParameters {
+            kp,
+            ki,
+            kd,
+            model_k: self.k,
+            model_t1: self.t1.get::<second>(),
+        }
+    }
+
+    /// Returns the parameters of the controller with the PI gains by `rule`.
+    ///
+    /// With the feedforward and the PI feedback, the integral of the error `z` follows
+    /// `t1 z'' + (1 + k kp) z' + k ki z = 0`.
+    pub fn tune(&self, rule: &TuningRule) -> Result<ControlParameters, TuningError> {
+        let t1 = self.t1.get::<second>();
+        let (kp, ki) = match *rule {
+            TuningRule::PolePlacement {
+                natural_frequency,
+                damping_ratio,
+            } => {
+                let damping = 2.0 * damping_ratio * natural_frequency * t1;
+                if damping.is_nan() || damping < 1.0 {
+                    return Err(TuningError::InsufficientBandwidth);
+                }
+                (
+                    (damping - 1.0) / self.k,
+                    natural_frequency * natural_frequency * t1 / self.k,
+                )
+            }
+            TuningRule::Imc { time_constant } => {
+                let lambda = time_constant.get::<second>();
+                if lambda.is_nan() || lambda <= 0.0 {
+                    return Err(TuningError::InvalidTimeConstant);
+                }
+                (t1 / (self.k * lambda), 1.0 / (self.k * lambda))
+            }
+        };
+        Ok(self.with_gains(kp, ki, 0.0))
+    }
+}
+
+// The sums of the normal equations for `y[n] = a * y[n - 1] + b * u[n]`.
+#[derive(Clone, Copy, Default, Debug)]
+struct LeastSquares {
+    yy: f32,
+    yu: f32,
+    uu: f32,
+    yz: f32,
+    uz: f32,
+}
+
+impl LeastSquares {
+    fn push(&mut self, last: f32, input: f32, current: f32) {
+        self.yy += last * last;
+        self.yu += last * input;
+        self.uu += input * input;
+        self.yz += last * current;
+        self.uz += input * current;
+    }
+
+    fn solve(&self, period: Time) -> Result<FirstOrderModel, IdentificationError> {
+        let det = self.yy * self.uu - self.yu * self.yu;
+        if det <= f32::EPSILON * self.yy * self.uu {
+            return Err(IdentificationError::InsufficientExcitation);
+        }
+        let a = (self.uu * self.yz - self.yu * self.uz) / det;
+        let b = (self.yy * self.uz - self.yu * self.yz) / det;
+        if !(0.0..1.0).contains(&a) {
+            return Err(IdentificationError::Unstable);
+        }
+        // The inverse of the backward Euler discretization with the time constant `t1`.
+        Ok(FirstOrderModel {
+            k: b / (1.0 - a),
+            t1: period * a / (1.0 - a),
+        })
+    }
+}
+
+/// Fits first-order models of both channels from applied voltages and sensor values.
+///
+/// The translational velocity is the mean of the encoders over a period, so it is fitted
+/// to the mean of the last two inputs, while the angular velocity from the gyroscope is fitted
+/// to the last input.
+#[derive(Debug, TypedBuilder)]
+pub struct Identifier {
+    period: Time,
+    #[builder(default, setter(skip))]
+    translational: LeastSquares,
+    #[builder(default, setter(skip))]
+    rotational: LeastSquares,
+    #[builder(default, setter(skip))]
+    last: Option<(f32, f32, f32)>,
+}
+
+impl Identifier {
+    /// Records the sensor value measured after the period in which `voltage` was applied.
+    pub fn push(&mut self, voltage: &MotorOutput, value: &SensorValue) {
+        let trans_input = (voltage.left + voltage.right).get::<volt>() / 2.0;
+        let rot_input = (voltage.right - voltage.left).get::<volt>() / 2.0;
+        let trans_vel = ((value.left_distance + value.right_distance) / 2.0 / self.period).value;
+        let rot_vel = value.angular_velocity.value;
+        if let Some((last_trans_input, last_trans_vel, last_rot_vel)) = self.last {
+            self.translational.push(
+                last_trans_vel,
+                (trans_input + last_trans_input) / 2.0,
+                trans_vel,
+            );
+            self.rotational.push(last_rot_vel, rot_input, rot_vel);
+        }
+        self.last = Some((trans_input, trans_vel, rot_vel));
+    }
+
+    /// Returns the fitted translational model.
+    pub fn translational(&self) -> Result<FirstOrderModel, IdentificationError> {
+        self.translational.solve(self.period)
+    }
+
+    /// Returns the fitted rotational model.
+    pub fn rotational(&self) -> Result<FirstOrderModel, IdentificationError> {
+        self.rotational.solve(self.period)
+    }
+
+    /// Clears the recorded values.
+    pub fn reset(&mut self) {
+        self.translational = Default::default();
+        self.rotational = Default::default();
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use uom::si::{
+        angular_velocity::radian_per_second,
+        f32::{AngularVelocity, Length},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_identify_synthetic() {
+        let period = Time::new::<second>(0.001);
+        let mut identifier = Identifier::builder().period(period).build();
+        let (k, alpha) = (2.0, 0.99);
+        let excitation = Excitation::Chirp {
+            amplitude: ElectricPotential::new::<volt>(1.0),
+            start: Frequency::new::<hertz>(0.1),
+            end: Frequency::new::<hertz>(10.0),
+            duration: Time::new::<second>(2.0),
+        };
+        let mut omega = 0.0;
+        for i in 0..2000 {
+            let voltage = excitation.rotational(period * i as f32);
+            omega = alpha * omega + (1.0 - alpha) * k * voltage.right.get::<volt>();
+            identifier.push(
+                &voltage,
+                &SensorValue {
+                    left_distance: Length::default(),
+                    right_distance: Length::default(),
+                    translational_acceleration: Default::default(),
+                    angular_velocity: AngularVelocity::new::<radian_per_second>(omega),
+                },
+            );
+        }
+        let model = identifier.rotational().unwrap();
+        assert_relative_eq!(model.k, k, max_relative = 1e-3);
+        assert_relative_eq!(model.t1.get::<second>(), 0.099, max_relative = 1e-3);
+        assert_eq!(
+            identifier.translational(),
+            Err(IdentificationError::InsufficientExcitation)
+        );
+    }
+
+    // Return the error after `duration` from a unit step of the reference.
+    fn step_error(
+        model: &FirstOrderModel,
+        params: &ControlParameters,
+        feedforward: bool,
+        duration: f32,
+    ) -> f32 {
+        let dt = 0.0001;
+        let (mut y, mut sum) = (0.0, 0.0);
+        for _ in 0..(duration / dt) as usize {
+            let error = 1.0 - y;
+            sum += error * dt;
+            let u =
+                if feedforward { 1.0 / model.k } else { 0.0 } + params.kp * error + params.ki * sum;
+            y += (model.k * u - y) / model.t1.get::<second>() * dt;
+        }
+        1.0 - y
+    }
+
+    #[test]
+    fn test_tune() {
+        let models = [
+            FirstOrderModel {
+                k: 1.865,
+                t1: Time::new::<second>(0.4443),
+            },
+            FirstOrderModel {
+                k: 82.39,
+                t1: Time::new::<second>(0.2855),
+            },
+        ];
+        for model in models {
+            let params = model
+                .tune(&TuningRule::Imc {
+                    time_constant: Time::new::<second>(0.02),
+                })
+                .unwrap();
+            // the zero of the PI controller cancels the pole of the model.
+            assert_relative_eq!(
+                step_error(&model, &params, false, 0.02),
+                (-1.0f32).exp(),
+                epsilon = 0.01
+            );
+
+            // the error with the feedforward is `(1 - wt) exp(-wt)` with critical damping.
+            let params = model
+                .tune(&TuningRule::PolePlacement {
+                    natural_frequency: 50.0,
+                    damping_ratio: 1.0,
+                })
+                .unwrap();
+            for t in [0.01, 0.02, 0.05, 0.1] {
+                assert_relative_eq!(
+                    step_error(&model, &params, true, t),
+                    (1.0 - 50.0 * t) * (-50.0 * t).exp(),
+                    epsilon = 0.01
+                );
+            }
+
+            // the poles slower than the plant need a negative proportional gain.
+            let natural_frequency = 0.9 / (2.0 * model.t1.get::<second>());
+            assert_eq!(
+                model.tune(&TuningRule::PolePlacement {
+                    natural_frequency,
+                    damping_ratio: 1.0,
+                }),
+                Err(TuningError::InsufficientBandwidth)
+            );
+            assert_eq!(
+                model.tune(&TuningRule::Imc {
+                    time_constant: Time::default(),
+                }),
+                Err(TuningError::InvalidTimeConstant)
+            );
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use uom::si::{
+    electric_potential::volt,
+    f32::{Acceleration, AngularAcceleration, AngularVelocity, ElectricPotential, Time, Velocity},
+    time::second,
+};
+
+use crate::control::MotorOutput;
+
+/// Parameters of a DC motor which drives a wheel through a gear.
+///
+/// All values are in SI units.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MotorParameters {
+    /// The resistance of the winding in ohms.
+    pub resistance: f32,
+    /// The back-EMF constant in V/(rad/s).
+    pub back_emf_constant: f32,
+    /// The torque constant in N m/A.
+    pub torque_constant: f32,
+    /// The ratio of the rotational speed of the motor to that of the wheel.
+    pub gear_ratio: f32,
+    /// The radius of the wheel in meters.
+    pub wheel_radius: f32,
+    /// The Coulomb friction torque at the motor shaft in N m.
+    pub friction_torque: f32,
+}
+
+impl MotorParameters {
+    /// Returns the voltage to drive the wheel at `velocity` with `force` on the ground.
+    ///
+    /// The friction opposes the rotation, or the force if the wheel is stopped.
+    pub fn voltage(&self, velocity: f32, force: f32) -> f32 {
+        let omega = self.gear_ratio * velocity / self.wheel_radius;
+        let direction = if omega != 0.0 { omega } else { force };
+        let friction = if direction > 0.0 {
+            self.friction_torque
+        } else if direction < 0.0 {
+            -self.friction_torque
+        } else {
+            0.0
+        };
+        let torque = force * self.wheel_radius / self.gear_ratio + friction;
+        self.resistance * torque / self.torque_constant + self.back_emf_constant * omega
+    }
+}
+
+/// A model of a two-wheeled robot driven by DC motors.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MotorModel {
+    pub left: MotorParameters,
+    pub right: MotorParameters,
+    /// The mass of the robot in kilograms.
+    pub mass: f32,
+    /// The moment of inertia of the robot around its center in kg m^2.
+    pub inertia: f32,
+    /// The distance between the wheels in meters.
+    pub wheel_interval: f32,
+}
+
+impl MotorModel {
+    /// Returns the voltages of the motors which realize the velocities and the accelerations.
+    pub fn feedforward(
+        &self,
+        v: Velocity,
+        a: Acceleration,
+        omega: AngularVelocity,
+        alpha: AngularAcceleration,
+    ) -> MotorOutput {
+        let half = self.wheel_interval / 2.0;
+        let force = self.mass * a.value / 2.0;
+        let rot_force = self.inertia * alpha.value / self.wheel_interval;
+        let left = self
+            .left
+            .voltage(v.value - omega.value * half, force - rot_force);
+        let right = self
+            .right
+            .voltage(v.value + omega.value * half, force + rot_force);
+        MotorOutput {
+            left: ElectricPotential::new::<volt>(left),
+            right: ElectricPotential::new::<volt>(right),
+        }
+    }
+
+    /// Returns the gain and the time constant of the translational first-order model,
+    /// which equals this model without friction if both motors are the same as the left one.
+    pub fn translational_first_order(&self) -> (f32, Time) {
+        let MotorParameters {
+            resistance,
+            back_emf_constant,
+            torque_constant,
+            gear_ratio,
+            wheel_radius,
+            ..
+        } = self.left;
+        let k = wheel_radius / (back_emf_constant * gear_ratio);
+        let t1 = self.mass * wheel_radius * wheel_radius * resistance
+            / (2.0 * gear_ratio * gear_ratio * torque_constant * back_emf_constant);
+        (k, Time::new::<second>(t1))
+    }
+
+    /// Returns the gain and the time constant of the rotational first-order model,
+    /// which equals this model without friction if both motors are the same as the left one.
+    pub fn rotational_first_order(&self) -> (f32, Time) {
+        let (k, _) = self.translational_first_order();
+        let MotorParameters {
+            resistance,
+            back_emf_constant,
+            torque_constant,
+            gear_ratio,
+            wheel_radius,
+            ..
+        } = self.left;
+        let half = self.wheel_interval / 2.0;
+        let t1 = self.inertia * wheel_radius * wheel_radius * resistance
+            / (2.0 * half * half * gear_ratio * gear_ratio * torque_constant * back_emf_constant);
+        (k / half, Time::new::<second>(t1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use uom::si::{
+        acceleration::meter_per_second_squared, angular_acceleration::radian_per_second_squared,
+        angular_velocity::radian_per_second, velocity::meter_per_second,
+    };
+
+    use super::*;
+
+    fn model(friction_torque: f32) -> MotorModel {
+        let motor = MotorParameters {
+            resistance: 1.07,
+            back_emf_constant: 0.000_207,
+            torque_constant: 0.000_207,
+            gear_ratio: 40.0 / 12.0,
+            wheel_radius: 0.006_75,
+            friction_torque,
+        };
+        MotorModel {
+            left: motor.clone(),
+            right: motor,
+            mass: 0.015,
+            inertia: 1.5e-6,
+            wheel_interval: 0.0335,
+        }
+    }
+
+    #[test]
+    fn test_feedforward_equals_first_order() {
+        let model = model(0.0);
+        let (trans_k, trans_t1) = model.translational_first_order();
+        let (rot_k, rot_t1) = model.rotational_first_order();
+        let test_cases = [
+            (0.5, 2.0, 0.0, 0.0),
+            (0.3, -1.0, 10.0, 200.0),
+            (0.0, 0.0, -5.0, 0.0),
+        ];
+        for (v, a, omega, alpha) in test_cases {
+            let output = model.feedforward(
+                Velocity::new::<meter_per_second>(v),
+                Acceleration::new::<meter_per_second_squared>(a),
+                AngularVelocity::new::<radian_per_second>(omega),
+                AngularAcceleration::new::<radian_per_second_squared>(alpha),
+            );
+            let vol_t = (a * trans_t1.value + v) / trans_k;
+            let vol_r = (alpha * rot_t1.value + omega) / rot_k;
+            assert_relative_eq!(
+                output.left.get::<volt>(),
+                vol_t - vol_r,
+                epsilon = 1e-4,
+                max_relative = 1e-4
+            );
+            assert_relative_eq!(
+                output.right.get::<volt>(),
+                vol_t + vol_r,
+                epsilon = 1e-4,
+                max_relative = 1e-4
+            );
+        }
+    }
+
+    #[test]
+    fn test_friction() {
+        let without = model(0.0);
+        let with = model(1e-5);
+        let feedforward = |model: &MotorModel, v| {
+            model.feedforward(
+                Velocity::new::<meter_per_second>(v),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            )
+        };
+        let output = feedforward(&with, 0.5);
+        let expected = feedforward(&without, 0.5);
+        assert!(output.left > expected.left && output.right > expected.right);
+        // friction does not act on stopped wheels without force.
+        let output = feedforward(&with, 0.0);
+        assert_eq!(output.left.get::<volt>(), 0.0);
+
+        // friction acts against the force to start stopped wheels.
+        let start = |model: &MotorModel, a| {
+            model.feedforward(
+                Default::default(),
+                Acceleration::new::<meter_per_second_squared>(a),
+                Default::default(),
+                Default::default(),
+            )
+        };
+        let output = start(&with, 1.0);
+        let expected = start(&without, 1.0);
+        assert!(output.left > expected.left && output.right > expected.right);
+        let output = start(&with, -1.0);
+        let expected = start(&without, -1.0);
+        assert!(output.left < expected.left && output.right < expected.right);
+    }
+}
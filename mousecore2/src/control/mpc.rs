@@ -0,0 +1,373 @@
+#[allow(unused_imports)]
+use micromath::F32Ext;
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+use uom::si::{
+    acceleration::meter_per_second_squared,
+    angular_acceleration::radian_per_second_squared,
+    angular_velocity::radian_per_second,
+    electric_potential::volt,
+    f32::{Acceleration, AngularAcceleration, AngularVelocity, ElectricPotential, Time, Velocity},
+    velocity::meter_per_second,
+};
+
+use super::{
+    identify::FirstOrderModel, normalize_angle, AngleTarget, ControlTarget, LengthTarget, Target,
+    TargetTracker,
+};
+use crate::estimate::State;
+
+/// The weights of the cost of [`MpcTracker`].
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MpcWeights {
+    /// The weight of the longitudinal and lateral errors in 1/m^2.
+    pub position: f32,
+    /// The weight of the heading error in 1/rad^2.
+    pub angle: f32,
+    /// The weight of the translational velocity error in s^2/m^2.
+    pub velocity: f32,
+    /// The weight of the angular velocity error in s^2/rad^2.
+    pub angular_velocity: f32,
+    /// The weight of the deviation of the motor voltages from the feedforward in 1/V^2.
+    pub voltage: f32,
+}
+
+impl Default for MpcWeights {
+    fn default() -> Self {
+        Self {
+            position: 1e6,
+            angle: 1e3,
+            velocity: 10.0,
+            angular_velocity: 0.1,
+            voltage: 0.1,
+        }
+    }
+}
+
+// The error of the state in the frame of the reference: longitudinal, lateral, heading,
+// translational velocity and angular velocity.
+type Error = [f32; 5];
+// The deviation of the left and right motor voltages from the feedforward.
+type Input = [f32; 2];
+
+/// A tracker with a linear time-varying model predictive control.
+///
+/// The error dynamics of the unicycle with first-order motor models are linearized along
+/// the reference over `N` steps of `step` periods, and the deviations of the motor voltages
+/// are optimized within `max_voltage`.
+#[derive(Debug, TypedBuilder)]
+pub struct MpcTracker<const N: usize> {
+    period: Time,
+    /// The number of periods between the steps of the prediction.
+    #[builder(default = 10)]
+    step: usize,
+    trans_model: FirstOrderModel,
+    rot_model: FirstOrderModel,
+    max_voltage: ElectricPotential,
+    #[builder(default)]
+    weights: MpcWeights,
+    /// The number of iterations of the solver.
+    #[builder(default = 50)]
+    iterations: usize,
+    #[builder(default = [[0.0; 2]; N], setter(skip))]
+    solution: [Input; N],
+    // the last target of the last call, which is held while no targets are given
+    #[builder(default, setter(skip))]
+    held: Option<Target>,
+}
+
+impl<const N: usize> MpcTracker<N> {
+    /// Returns the targets and the current values for [`Controller`](super::Controller)
+    /// in the same way as [`Tracker::track`](super::Tracker::track).
+    ///
+    /// `targets` starts from the current target and the last one is held beyond its end.
+    /// Empty `targets` hold the last target of the previous call, or the current pose
+    /// at rest if there is none.
+    pub fn track(&mut self, state: &State, targets: &[Target]) -> (ControlTarget, ControlTarget) {
+        let held;
+        let targets = match targets.last() {
+            Some(last) => {
+                self.held = Some(*last);
+                targets
+            }
+            None => {
+                held = self.held.unwrap_or(Target {
+                    x: LengthTarget {
+                        x: state.x.x,
+                        ..Default::default()
+                    },
+                    y: LengthTarget {
+                        x: state.y.x,
+                        ..Default::default()
+                    },
+                    theta: AngleTarget {
+                        x: state.theta.x,
+                        ..Default::default()
+                    },
+                });
+                core::slice::from_ref(&held)
+            }
+        };
+        let sin_th = state.theta.x.value.sin();
+        let cos_th = state.theta.x.value.cos();
+        let v = state.x.v * cos_th + state.y.v * sin_th;
+        let a = state.x.a * cos_th + state.y.a * sin_th;
+        let current = ControlTarget {
+            v,
+            a,
+            omega: state.theta.v,
+            alpha: state.theta.a,
+        };
+
+        let dt = (self.period * self.step as f32).value;
+        let (trans_k, trans_t1) = (self.trans_model.k, self.trans_model.t1.value);
+        let (rot_k, rot_t1) = (self.rot_model.k, self.rot_model.t1.value);
+        let trans_decay = (-dt / trans_t1).exp();
+        let rot_decay = (-dt / rot_t1).exp();
+        let b_v = (1.0 - trans_decay) * trans_k / 2.0;
+        let b_w = (1.0 - rot_decay) * rot_k / 2.0;
+
+        let reference = |k: usize| {
+            let target = &targets[(k * self.step).min(targets.len() - 1)];
+            let sin_th = target.theta.x.value.sin();
+            let cos_th = target.theta.x.value.cos();
+            let v = target.x.v * cos_th + target.y.v * sin_th;
+            let a = target.x.a * cos_th + target.y.a * sin_th;
+            let ut = (trans_t1 * a.value + v.value) / trans_k;
+            let ur = (rot_t1 * target.theta.a.value + target.theta.v.value) / rot_k;
+            (target, v.value, target.theta.v.value, [ut - ur, ut + ur])
+        };
+
+        // The initial error.
+        let (target, vr, wr, _) = reference(0);
+        let sin_r = target.theta.x.value.sin();
+        let cos_r = target.theta.x.value.cos();
+        let dx = (state.x.x - target.x.x).value;
+        let dy = (state.y.x - target.y.x).value;
+        let mut free: [Error; N] = [[0.0; 5]; N];
+        let mut e = [
+            cos_r * dx + sin_r * dy,
+            -sin_r * dx + cos_r * dy,
+            normalize_angle(state.theta.x - target.theta.x).value,
+            v.value - vr,
+            state.theta.v.value - wr,
+        ];
+
+        // `effect[k][j]` is the effect of the input at step `j` on the error at step `k + 1`.
+        let mut effect = [[[[0.0; 2]; 5]; N]; N];
+        let mut lower = [[0.0; 2]; N];
+        let mut upper = [[0.0; 2]; N];
+        let max_voltage = self.max_voltage.get::<volt>();
+        for k in 0..N {
+            let (_, vr, wr, feedforward) = reference(k);
+            let propagate = |e: &Error| {
+                [
+                    e[0] + dt * (wr * e[1] + e[3]),
+                    e[1] + dt * (-wr * e[0] + vr * e[2]),
+                    e[2] + dt * e[4],
+                    trans_decay * e[3],
+                    rot_decay * e[4],
+                ]
+            };
+            e = propagate(&e);
+            free[k] = e;
+            for j in 0..k {
+                let mut column = [[0.0; 5]; 2];
+                for (i, column) in column.iter_mut().enumerate() {
+                    let previous = [
+                        effect[k - 1][j][0][i],
+                        effect[k - 1][j][1][i],
+                        effect[k - 1][j][2][i],
+                        effect[k - 1][j][3][i],
+                        effect[k - 1][j][4][i],
+                    ];
+                    *column = propagate(&previous);
+                }
+                for (row, effect) in effect[k][j].iter_mut().enumerate() {
+                    *effect = [column[0][row], column[1][row]];
+                }
+            }
+            effect[k][k][3] = [b_v, b_v];
+            effect[k][k][4] = [-b_w, b_w];
+            for i in 0..2 {
+                lower[k][i] = -max_voltage - feedforward[i];
+                upper[k][i] = max_voltage - feedforward[i];
+            }
+        }
+
+        let weights = &self.weights;
+        let q = [
+            weights.position,
+            weights.position,
+            weights.angle,
+            weights.velocity,
+            weights.angular_velocity,
+        ];
+        // The Hessian and the gradient at zero of the condensed QP.
+        let mut hessian = [[[[0.0; 2]; 2]; N]; N];
+        let mut gradient = [[0.0; 2]; N];
+        for j in 0..N {
+            for l in 0..N {
+                for effect in &effect[j.max(l)..] {
+                    for a in 0..2 {
+                        for b in 0..2 {
+                            hessian[j][l][a][b] += (0..5)
+                                .map(|r| effect[j][r][a] * q[r] * effect[l][r][b])
+                                .sum::<f32>();
+                        }
+                    }
+                }
+            }
+            hessian[j][j][0][0] += weights.voltage;
+            hessian[j][j][1][1] += weights.voltage;
+            for k in j..N {
+                for a in 0..2 {
+                    gradient[j][a] += (0..5)
+                        .map(|r| effect[k][j][r][a] * q[r] * free[k][r])
+                        .sum::<f32>();
+                }
+            }
+        }
+
+        let solution = self.solve(&hessian, &gradient, &lower, &upper);
+        self.solution = solution;
+
+        let (_, _, _, feedforward) = reference(0);
+        let left = feedforward[0] + solution[0][0];
+        let right = feedforward[1] + solution[0][1];
+        let ut = (left + right) / 2.0;
+        let ur = (right - left) / 2.0;
+        let a = (trans_k * ut - v.value) / trans_t1;
+        let alpha = (rot_k * ur - state.theta.v.value) / rot_t1;
+        let period = self.period.value;
+        (
+            ControlTarget {
+                v: Velocity::new::<meter_per_second>(v.value + a * period),
+                a: Acceleration::new::<meter_per_second_squared>(a),
+                omega: AngularVelocity::new::<radian_per_second>(
+                    state.theta.v.value + alpha * period,
+                ),
+                alpha: AngularAcceleration::new::<radian_per_second_squared>(alpha),
+            },
+            current,
+        )
+    }
+
+    // Minimize `x^T H x / 2 + g^T x` within the box with the accelerated projected gradient,
+    // starting from the last solution.
+    fn solve(
+        &self,
+        hessian: &[[[Input; 2]; N]; N],
+        gradient: &[Input; N],
+        lower: &[Input; N],
+        upper: &[Input; N],
+    ) -> [Input; N] {
+        // The Gershgorin bound of the largest eigenvalue.
+        let lipschitz = (0..N)
+            .flat_map(|j| (0..2).map(move |a| (j, a)))
+            .map(|(j, a)| {
+                (0..N)
+                    .map(|l| hessian[j][l][a][0].abs() + hessian[j][l][a][1].abs())
+                    .sum::<f32>()
+            })
+            .fold(f32::EPSILON, f32::max);
+        let project = |x: &mut [Input; N]| {
+            for j in 0..N {
+                for a in 0..2 {
+                    x[j][a] = x[j][a].max(lower[j][a]).min(upper[j][a]);
+                }
+            }
+        };
+
+        let mut x = self.solution;
+        project(&mut x);
+        let mut y = x;
+        let mut t = 1.0f32;
+        for _ in 0..self.iterations {
+            let mut next = y;
+            for j in 0..N {
+                for a in 0..2 {
+                    let g = gradient[j][a]
+                        + (0..N)
+                            .map(|l| hessian[j][l][a][0] * y[l][0] + hessian[j][l][a][1] * y[l][1])
+                            .sum::<f32>();
+                    next[j][a] -= g / lipschitz;
+                }
+            }
+            project(&mut next);
+            let t_next = (1.0 + (1.0 + 4.0 * t * t).sqrt()) / 2.0;
+            let momentum = (t - 1.0) / t_next;
+            for j in 0..N {
+                for a in 0..2 {
+                    y[j][a] = next[j][a] + momentum * (next[j][a] - x[j][a]);
+                }
+            }
+            x = next;
+            t = t_next;
+        }
+        x
+    }
+
+    pub fn reset(&mut self) {
+        self.solution = [[0.0; 2]; N];
+        self.held = None;
+    }
+}
+
+impl<const N: usize> TargetTracker for MpcTracker<N> {
+    fn track_targets(
+        &mut self,
+        state: &State,
+        targets: &[Target],
+    ) -> (ControlTarget, ControlTarget) {
+        self.track(state, targets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::control::fixtures::{mpc_tracker, state, straight_path};
+
+    #[test]
+    fn test_mpc_lateral_error() {
+        let mut tracker = mpc_tracker();
+        let targets = straight_path(0.0, 0.5, 0.0);
+        let (on_path, _) = tracker.track(&state(0.0, 0.0, 0.0, 0.5), &targets);
+        assert!(on_path.omega.value.abs() < 1e-3);
+
+        // the robot on the left of the path turns to the right.
+        tracker.reset();
+        let (target, _) = tracker.track(&state(0.0, 5.0, 0.0, 0.5), &targets);
+        assert!(target.alpha.value < 0.0);
+    }
+
+    #[test]
+    fn test_mpc_voltage_limit() {
+        let mut tracker = mpc_tracker();
+        let targets = straight_path(0.0, 0.0, 100.0);
+        let (target, current) = tracker.track(&state(0.0, 0.0, 0.0, 0.0), &targets);
+        // the voltage which realizes the acceleration of the output.
+        let voltage = (0.4443 * target.a.value + current.v.value) / 1.865;
+        assert!(voltage <= 3.7 + 1e-3, "{}", voltage);
+        assert!(voltage > 3.0);
+    }
+
+    #[test]
+    fn test_mpc_empty_targets() {
+        let mut tracker = mpc_tracker();
+        // the robot at rest stays there without targets.
+        let (target, _) = tracker.track(&state(10.0, 5.0, 30.0, 0.0), &[]);
+        assert!(target.v.value.abs() < 1e-3);
+        assert!(target.omega.value.abs() < 1e-3);
+
+        // the last target is held after the targets run out.
+        let targets = straight_path(0.0, 0.5, 0.0);
+        let robot = state(0.0, 0.0, 0.0, 0.5);
+        let mut expected = mpc_tracker();
+        expected.track(&robot, &targets);
+        let expected = expected.track(&robot, &targets[targets.len() - 1..]);
+        tracker.reset();
+        tracker.track(&robot, &targets);
+        assert_eq!(tracker.track(&robot, &[]), expected);
+    }
+}
@@ -0,0 +1,262 @@
+#[allow(unused_imports)]
+use micromath::F32Ext;
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+use uom::si::{
+    angular_velocity::radian_per_second,
+    f32::{AngularAcceleration, AngularVelocity, Frequency, Length, Time, Velocity},
+};
+
+use super::{normalize_angle, ControlTarget, Target};
+use crate::estimate::State;
+
+/// A law to steer the robot onto the path.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum PathTrackingLaw {
+    /// Steers along the arc through the first point of the path at `lookahead` from the robot.
+    /// Near the end of the path, the point is extended along the heading of the last target.
+    PurePursuit { lookahead: Length },
+    /// Steers by the heading error and the arctangent of the lateral error
+    /// divided by the velocity plus `softening`, and turns at `heading_gain` times the sum
+    /// in addition to the curvature of the path.
+    Stanley {
+        gain: Frequency,
+        softening: Velocity,
+        heading_gain: Frequency,
+    },
+}
+
+/// A tracker which follows the geometric path of a trajectory instead of its timing.
+///
+/// The robot is projected onto the nearest target of the path, and the velocity is taken
+/// from the target `preview` periods ahead of the projection, so the robot never cuts
+/// corners to catch up with a target which has gone ahead.
+///
+/// The projection is a greedy search which stops at the first local minimum of the distance,
+/// so the path should be a single segment, e.g. a straight or a slalom, and the tracker should be
+/// [`reset`](Self::reset) at the start of each segment.
+#[derive(Debug, TypedBuilder)]
+pub struct PathTracker {
+    period: Time,
+    law: PathTrackingLaw,
+    /// The number of targets between the projection and the reference of the velocity.
+    #[builder(default = 10)]
+    preview: usize,
+    #[builder(default, setter(skip))]
+    index: usize,
+    // `None` until the first track, where the angular velocity of the robot is used instead.
+    #[builder(default, setter(skip))]
+    last_omega: Option<AngularVelocity>,
+}
+
+impl PathTracker {
+    // The velocity under which the curvature of the path is regarded as zero.
+    const MIN_VELOCITY: f32 = 1e-3;
+
+    /// Returns the targets and the current values for [`Controller`](super::Controller)
+    /// in the same way as [`Tracker::track`](super::Tracker::track).
+    ///
+    /// `path` is the trajectory of the current segment and the projection only moves forward
+    /// from the last one.
+    pub fn track(&mut self, state: &State, path: &[Target]) -> (ControlTarget, ControlTarget) {
+        assert!(!path.is_empty());
+        let sin_th = state.theta.x.value.sin();
+        let cos_th = state.theta.x.value.cos();
+        let current = ControlTarget {
+            v: state.x.v * cos_th + state.y.v * sin_th,
+            a: state.x.a * cos_th + state.y.a * sin_th,
+            omega: state.theta.v,
+            alpha: state.theta.a,
+        };
+
+        let (x, y) = (state.x.x.value, state.y.x.value);
+        let distance = |target: &Target| (target.x.x.value - x).hypot(target.y.x.value - y);
+        self.index = self.index.min(path.len() - 1);
+        while self.index + 1 < path.len()
+            && distance(&path[self.index + 1]) <= distance(&path[self.index])
+        {
+            self.index += 1;
+        }
+
+        let nearest = &path[self.index];
+        let preview = &path[(self.index + self.preview).min(path.len() - 1)];
+        let sin_r = preview.theta.x.value.sin();
+        let cos_r = preview.theta.x.value.cos();
+        let v = preview.x.v * cos_r + preview.y.v * sin_r;
+        let a = preview.x.a * cos_r + preview.y.a * sin_r;
+
+        let omega = match self.law {
+            PathTrackingLaw::PurePursuit { lookahead } => {
+                let lookahead = lookahead.value;
+                let (gx, gy) = match path[self.index..]
+                    .iter()
+                    .find(|target| distance(target) >= lookahead)
+                {
+                    Some(goal) => (goal.x.x.value, goal.y.x.value),
+                    None => {
+                        let last = &path[path.len() - 1];
+                        let rest = lookahead - distance(last);
+                        (
+                            last.x.x.value + rest * last.theta.x.value.cos(),
+                            last.y.x.value + rest * last.theta.x.value.sin(),
+                        )
+                    }
+                };
+                let dx = gx - x;
+                let dy = gy - y;
+                let lateral = -sin_th * dx + cos_th * dy;
+                let squared = dx * dx + dy * dy;
+                if squared > f32::EPSILON {
+                    2.0 * v.value * lateral / squared
+                } else {
+                    0.0
+                }
+            }
+            PathTrackingLaw::Stanley {
+                gain,
+                softening,
+                heading_gain,
+            } => {
+                let sin_n = nearest.theta.x.value.sin();
+                let cos_n = nearest.theta.x.value.cos();
+                let lateral = -sin_n * (x - nearest.x.x.value) + cos_n * (y - nearest.y.x.value);
+                let vn = nearest.x.v.value * cos_n + nearest.y.v.value * sin_n;
+                let curvature = if vn.abs() > Self::MIN_VELOCITY {
+                    nearest.theta.v.value / vn
+                } else {
+                    0.0
+                };
+                let heading = normalize_angle(nearest.theta.x - state.theta.x).value;
+                let steer = heading
+                    - (gain.value * lateral / (softening.value + current.v.value.abs())).atan();
+                curvature * v.value + heading_gain.value * steer
+            }
+        };
+        let omega = AngularVelocity::new::<radian_per_second>(omega);
+        let last_omega = self.last_omega.unwrap_or(state.theta.v);
+        let alpha = AngularAcceleration::from((omega - last_omega) / self.period);
+        self.last_omega = Some(omega);
+
+        (ControlTarget { v, a, omega, alpha }, current)
+    }
+
+    /// Returns the index of the target onto which the robot was projected.
+    pub fn progress(&self) -> usize {
+        self.index
+    }
+
+    /// Returns true if the velocity is taken from the end of `path`.
+    pub fn is_finished(&self, path: &[Target]) -> bool {
+        self.index + self.preview + 1 >= path.len()
+    }
+
+    /// Restarts the tracking from the beginning of a new path.
+    pub fn reset(&mut self) {
+        self.index = 0;
+        self.last_omega = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uom::si::{frequency::hertz, length::millimeter, velocity::meter_per_second};
+
+    use super::*;
+    use crate::control::fixtures::{path_tracker, state, straight_path};
+
+    // Return a state at `along` in mm on the path to the north east shifted by `lateral`
+    // in mm to the left.
+    fn diagonal_state(along: f32, lateral: f32) -> State {
+        let (sin, cos) = core::f32::consts::FRAC_PI_4.sin_cos();
+        state(
+            along * cos - lateral * sin,
+            along * sin + lateral * cos,
+            45.0,
+            0.0,
+        )
+    }
+
+    fn laws() -> [PathTrackingLaw; 2] {
+        [
+            PathTrackingLaw::PurePursuit {
+                lookahead: Length::new::<millimeter>(30.0),
+            },
+            PathTrackingLaw::Stanley {
+                gain: Frequency::new::<hertz>(10.0),
+                softening: Velocity::new::<meter_per_second>(0.1),
+                heading_gain: Frequency::new::<hertz>(20.0),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_steer_to_path() {
+        let path = straight_path(45.0, 1.0, 0.0);
+        for law in laws() {
+            for (lateral, sign) in [(5.0, -1.0), (-5.0, 1.0), (0.0, 0.0)] {
+                let mut tracker = path_tracker(law);
+                let (target, _) = tracker.track(&diagonal_state(100.0, lateral), &path);
+                assert!(
+                    target.omega.value * sign > 0.0
+                        || sign == 0.0 && target.omega.value.abs() < 1e-3,
+                    "{:?}, {}: {:?}",
+                    law,
+                    lateral,
+                    target.omega
+                );
+                assert!((target.v.value - 1.0).abs() < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_projection_does_not_chase_time() {
+        let path = straight_path(45.0, 1.0, 0.0);
+        let mut tracker = path_tracker(laws()[0]);
+        // the robot lags behind the time-indexed target far away, and is projected onto its own
+        // position instead.
+        tracker.track(&diagonal_state(50.0, 0.0), &path);
+        assert_eq!(tracker.progress(), 50);
+        assert!(!tracker.is_finished(&path));
+
+        // the projection never moves backward.
+        tracker.track(&diagonal_state(20.0, 0.0), &path);
+        assert_eq!(tracker.progress(), 50);
+
+        tracker.track(&diagonal_state(495.0, 0.0), &path);
+        assert!(tracker.is_finished(&path));
+        tracker.reset();
+        assert_eq!(tracker.progress(), 0);
+    }
+
+    #[test]
+    fn test_pursuit_near_end() {
+        let path = straight_path(45.0, 1.0, 0.0);
+        let mut tracker = path_tracker(laws()[0]);
+        // the lookahead point beyond the end keeps the arc as wide as on the way.
+        let (target, _) = tracker.track(&diagonal_state(495.0, 1.0), &path);
+        assert!(target.omega.value < 0.0 && target.omega.value > -10.0);
+    }
+
+    #[test]
+    fn test_angular_acceleration_after_reset() {
+        let path = straight_path(45.0, 1.0, 0.0);
+        for law in laws() {
+            let mut tracker = path_tracker(law);
+            let mut turning = diagonal_state(100.0, 5.0);
+            let (first, _) = tracker.track(&turning, &path);
+            assert!(first.omega.value.abs() > 1.0);
+
+            // the robot already turns at the commanded angular velocity when a new segment starts.
+            turning.theta.v = first.omega;
+            tracker.reset();
+            let (target, _) = tracker.track(&turning, &path);
+            assert!(
+                target.alpha.value.abs() < 1e-2,
+                "{:?}: {:?}",
+                law,
+                target.alpha
+            );
+        }
+    }
+}
@@ -1,5 +1,6 @@
 #![cfg_attr(not(test), no_std)]
 
+pub mod config;
 pub mod control;
 pub mod estimate;
 pub mod solve;
@@ -1,2 +1,4 @@
+pub mod record;
 pub mod run;
 pub mod search;
+pub mod velocity;
@@ -0,0 +1,419 @@
+use core::fmt;
+
+use heapless::HistoryBuffer;
+use serde::{Deserialize, Serialize};
+use uom::si::f32::Time;
+
+use crate::solve::search::{
+    Commander, Coordinate, Posture, SearchError, SearchErrorKind, SearchPhase, SearchState,
+    SearchStrategy, TrajectoryKind, WallState,
+};
+use crate::wall::Walls;
+use crate::WIDTH;
+
+/// An event which occurred during search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchEvent<const W: u8> {
+    /// The strategy entered the given phase.
+    PhaseChanged(SearchPhase),
+    /// The robot started to move and will be at `state` after the move.
+    Moved {
+        state: SearchState<W>,
+        kind: TrajectoryKind,
+    },
+    /// The commander started to wait for wall data at `state`.
+    Waited { state: SearchState<W> },
+    /// The search finished at `state`.
+    Finished { state: SearchState<W> },
+    /// The search failed at `state`.
+    ///
+    /// Each of the `walls` walls reported by the error follows as [`SearchEvent::ReportedWall`].
+    Failed {
+        state: SearchState<W>,
+        error: SearchErrorKind,
+        walls: u8,
+    },
+    /// A wall reported by the last failure.
+    ReportedWall(Coordinate<W>),
+}
+
+/// A [`SearchEvent`] with the time when it occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SearchRecord<const W: u8> {
+    pub time: Time,
+    pub event: SearchEvent<W>,
+}
+
+/// Statistics of a search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchStats {
+    pub fronts: u16,
+    pub turns: u16,
+    pub back_turns: u16,
+    /// The number of times [`Commander::next_coordinate`] returned `Ok(None)`.
+    pub waits: u32,
+    /// The number of distinct cells the robot entered.
+    pub visited_cells: u16,
+    pub explore_time: Time,
+    pub to_goal_time: Time,
+    pub return_time: Time,
+}
+
+impl SearchStats {
+    /// Returns the elapsed time in the given phase.
+    pub fn phase_time(&self, phase: SearchPhase) -> Time {
+        match phase {
+            SearchPhase::Explore => self.explore_time,
+            SearchPhase::ToGoal => self.to_goal_time,
+            SearchPhase::Return => self.return_time,
+        }
+    }
+}
+
+// Return the cell the robot with the given state is entering.
+fn cell<const W: u8>(state: &SearchState<W>) -> (u8, u8) {
+    let (x, y) = (state.x(), state.y());
+    match state.posture() {
+        Posture::North => (x >> 1, (y + 1) >> 1),
+        Posture::East => ((x + 1) >> 1, y >> 1),
+        Posture::South | Posture::West => (x >> 1, y >> 1),
+    }
+}
+
+/// A wrapper of [`SearchStrategy`] which records events of search into a ring buffer.
+///
+/// Only the latest `N` events are kept while [`SearchStats`] covers the whole search.
+/// The robot should pass commanders and moves through this recorder
+/// and call [`tick`](Self::tick) every control period.
+pub struct SearchRecorder<S, const W: u8, const N: usize> {
+    strategy: S,
+    records: HistoryBuffer<SearchRecord<W>, N>,
+    stats: SearchStats,
+    visited: [u32; WIDTH],
+    time: Time,
+    phase: Option<SearchPhase>,
+    waiting: bool,
+}
+
+impl<S, const W: u8, const N: usize> SearchRecorder<S, W, N> {
+    pub fn new(strategy: S) -> Self {
+        Self {
+            strategy,
+            records: HistoryBuffer::new(),
+            stats: Default::default(),
+            visited: [0; WIDTH],
+            time: Default::default(),
+            phase: None,
+            waiting: false,
+        }
+    }
+
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    /// Returns recorded events from the oldest one.
+    pub fn records(&self) -> impl Iterator<Item = &SearchRecord<W>> {
+        self.records.oldest_ordered()
+    }
+
+    /// Advances the clock of the recorder.
+    pub fn tick(&mut self, period: Time) {
+        self.time += period;
+        match self.phase {
+            Some(SearchPhase::Explore) => self.stats.explore_time += period,
+            Some(SearchPhase::ToGoal) => self.stats.to_goal_time += period,
+            Some(SearchPhase::Return) => self.stats.return_time += period,
+            None => (),
+        }
+    }
+
+    /// Calls [`Commander::next_coordinate`] and records waiting for wall data.
+    pub fn next_coordinate(
+        &mut self,
+        state: &SearchState<W>,
+        commander: &Commander<W>,
+        wall_state: impl Fn(&Coordinate<W>) -> WallState,
+    ) -> Result<Option<Coordinate<W>>, SearchError<W>> {
+        let result = commander.next_coordinate(wall_state);
+        match &result {
+            Ok(Some(_)) => self.waiting = false,
+            Ok(None) => {
+                self.stats.waits += 1;
+                if !self.waiting {
+                    self.waiting = true;
+                    self.record(SearchEvent::Waited { state: *state });
+                }
+            }
+            Err(error) => self.record_error(state, error),
+        }
+        result
+    }
+
+    /// Calls [`SearchState::update`] and records the move.
+    pub fn update(
+        &mut self,
+        state: &mut SearchState<W>,
+        next_coord: &Coordinate<W>,
+    ) -> Option<TrajectoryKind> {
+        let kind = state.update(next_coord)?;
+        match kind {
+            TrajectoryKind::Front => self.stats.fronts += 1,
+            TrajectoryKind::Right | TrajectoryKind::Left => self.stats.turns += 1,
+            TrajectoryKind::Back => self.stats.back_turns += 1,
+        }
+        let (x, y) = cell(state);
+        let bit = 1 << x;
+        if self.visited[y as usize] & bit == 0 {
+            self.visited[y as usize] |= bit;
+            self.stats.visited_cells += 1;
+        }
+        self.record(SearchEvent::Moved {
+            state: *state,
+            kind,
+        });
+        Some(kind)
+    }
+
+    fn record(&mut self, event: SearchEvent<W>) {
+        self.records.write(SearchRecord {
+            time: self.time,
+            event,
+        });
+    }
+
+    fn record_error(&mut self, state: &SearchState<W>, error: &SearchError<W>) {
+        let walls = error.walls();
+        self.record(SearchEvent::Failed {
+            state: *state,
+            error: error.kind(),
+            walls: walls.len() as u8,
+        });
+        for &coord in walls {
+            self.record(SearchEvent::ReportedWall(coord));
+        }
+    }
+}
+
+impl<S, const W: u8, const N: usize> SearchStrategy<W> for SearchRecorder<S, W, N>
+where
+    S: SearchStrategy<W>,
+{
+    fn next_commander(
+        &mut self,
+        state: &SearchState<W>,
+        wall_state: impl Fn(&Coordinate<W>) -> WallState + Copy,
+        rank: impl Fn(&Coordinate<W>) -> u16 + Copy,
+    ) -> Result<Option<Commander<W>>, SearchError<W>> {
+        let result = self.strategy.next_commander(state, wall_state, rank);
+        let phase = self.strategy.phase();
+        if self.phase != Some(phase) {
+            self.phase = Some(phase);
+            self.record(SearchEvent::PhaseChanged(phase));
+        }
+        match &result {
+            Ok(Some(_)) => (),
+            Ok(None) => self.record(SearchEvent::Finished { state: *state }),
+            Err(error) => self.record_error(state, error),
+        }
+        result
+    }
+
+    fn phase(&self) -> SearchPhase {
+        self.strategy.phase()
+    }
+}
+
+/// A printout of recorded moves over walls.
+///
+/// Each cell shows how many times the robot entered it (`*` for more than 9 times)
+/// and the last state of the robot is shown by an arrow.
+/// Events other than moves are listed below the walls.
+pub struct Replay<'a, const W: u8> {
+    walls: &'a Walls<W>,
+    counts: [[u8; WIDTH]; WIDTH],
+    last: Option<SearchState<W>>,
+    records: &'a [SearchRecord<W>],
+}
+
+impl<'a, const W: u8> Replay<'a, W> {
+    pub fn new(walls: &'a Walls<W>, records: &'a [SearchRecord<W>]) -> Self {
+        let mut counts = [[0u8; WIDTH]; WIDTH];
+        let mut last = None;
+        for record in records {
+            if let SearchEvent::Moved { state, .. } = record.event {
+                let (x, y) = cell(&state);
+                let count = &mut counts[y as usize][x as usize];
+                *count = count.saturating_add(1);
+                last = Some(state);
+            }
+        }
+        Self {
+            walls,
+            counts,
+            last,
+            records,
+        }
+    }
+}
+
+impl<'a, const W: u8> fmt::Display for Replay<'a, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let exists = |x, y| {
+            matches!(
+                self.walls.wall_state(&Coordinate::new(x, y).unwrap()),
+                WallState::Checked { exists: true }
+            )
+        };
+        let last = self.last.map(|state| (cell(&state), state.posture()));
+        for y in (0..W).rev() {
+            for x in 0..W {
+                if exists(x << 1, (y << 1) | 1) {
+                    write!(f, "+---")?;
+                } else {
+                    write!(f, "+   ")?;
+                }
+            }
+            writeln!(f, "+")?;
+
+            write!(f, "|")?;
+            for x in 0..W {
+                let mark = match (last, self.counts[y as usize][x as usize]) {
+                    (Some((cell, posture)), _) if cell == (x, y) => match posture {
+                        Posture::North => '^',
+                        Posture::East => '>',
+                        Posture::South => 'v',
+                        Posture::West => '<',
+                    },
+                    (_, 0) => ' ',
+                    (_, count @ 1..=9) => (b'0' + count) as char,
+                    _ => '*',
+                };
+                write!(f, " {} ", mark)?;
+                if exists((x << 1) | 1, y << 1) {
+                    write!(f, "|")?;
+                } else {
+                    write!(f, " ")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        for _ in 0..W {
+            write!(f, "+---")?;
+        }
+        writeln!(f, "+")?;
+        for record in self.records {
+            if !matches!(record.event, SearchEvent::Moved { .. }) {
+                writeln!(f, "{:>9.3}s: {:?}", record.time.value, record.event)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::solve::search::AdachiSearcher;
+    use uom::si::time::second;
+
+    #[test]
+    fn test_record() {
+        let input = include_str!("../../mazes/maze4_1.dat");
+        let answer = input.parse::<Walls<4>>().unwrap();
+        let start = Coordinate::new(0, 1).unwrap();
+        let goals = [
+            Coordinate::new(2, 1).unwrap(),
+            Coordinate::new(3, 0).unwrap(),
+        ];
+        let period = Time::new::<second>(0.1);
+        let mut recorder = SearchRecorder::<_, 4, 64>::new(AdachiSearcher::new(start, &goals));
+        let mut state = SearchState::new(start, Posture::North).unwrap();
+
+        // all walls are known, so the commander never waits.
+        while let Some(commander) = recorder
+            .next_commander(&state, |coord| answer.wall_state(coord), |_| 0)
+            .unwrap()
+        {
+            let next = recorder
+                .next_coordinate(&state, &commander, |coord| answer.wall_state(coord))
+                .unwrap()
+                .unwrap();
+            recorder.update(&mut state, &next).unwrap();
+            recorder.tick(period);
+        }
+
+        let stats = recorder.stats();
+        let moves = stats.fronts + stats.turns + stats.back_turns;
+        assert_eq!(stats.waits, 0);
+        assert!(stats.visited_cells > 0);
+        assert_relative_eq!(
+            (stats.to_goal_time + stats.return_time).get::<second>(),
+            moves as f32 * period.get::<second>(),
+            epsilon = 1e-4
+        );
+
+        let records = recorder.records().copied().collect::<std::vec::Vec<_>>();
+        assert_eq!(
+            records.first().unwrap().event,
+            SearchEvent::PhaseChanged(SearchPhase::ToGoal)
+        );
+        assert!(records
+            .iter()
+            .any(|record| record.event == SearchEvent::PhaseChanged(SearchPhase::Return)));
+        assert_eq!(
+            records.last().unwrap().event,
+            SearchEvent::Finished { state }
+        );
+        let replay = std::format!("{}", Replay::new(&answer, &records));
+        assert!(replay.contains("Finished"));
+        assert_eq!(
+            replay.lines().filter(|line| line.starts_with('|')).count(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_record_failure() {
+        let mut walls = include_str!("../../mazes/maze4_1.dat")
+            .parse::<Walls<4>>()
+            .unwrap();
+        // misdetected wall
+        walls.update(
+            &Coordinate::new(2, 3).unwrap(),
+            &WallState::Checked { exists: true },
+        );
+        let start = Coordinate::new(0, 1).unwrap();
+        let goals = [
+            Coordinate::new(2, 1).unwrap(),
+            Coordinate::new(3, 0).unwrap(),
+        ];
+        let mut recorder = SearchRecorder::<_, 4, 32>::new(AdachiSearcher::new(start, &goals));
+        let state = SearchState::new(start, Posture::North).unwrap();
+        let error = recorder
+            .next_commander(&state, |coord| walls.wall_state(coord), |_| 0)
+            .unwrap_err();
+        assert!(!error.walls().is_empty());
+
+        let records = recorder.records().copied().collect::<std::vec::Vec<_>>();
+        let (failed, reported) = records.split_at(2);
+        assert_eq!(
+            failed[1].event,
+            SearchEvent::Failed {
+                state,
+                error: SearchErrorKind::GoalIsolated,
+                walls: error.walls().len() as u8,
+            }
+        );
+        assert_eq!(reported.len(), error.walls().len());
+        for (record, wall) in reported.iter().zip(error.walls()) {
+            assert_eq!(record.event, SearchEvent::ReportedWall(*wall));
+        }
+    }
+}
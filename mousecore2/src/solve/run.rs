@@ -9,7 +9,7 @@ use crate::trajectory::slalom::{SlalomDirection, SlalomKind};
 use crate::WIDTH;
 
 const QUE_MAX: usize = WIDTH * WIDTH * 16;
-const PATH_MAX: usize = WIDTH * WIDTH;
+pub(crate) const PATH_MAX: usize = WIDTH * WIDTH;
 const NEIGHBOR_MAX: usize = 2 * WIDTH + 4;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -143,7 +143,7 @@ impl<const W: u8> Node<W> {
         is_wall: impl Fn(&Coordinate<W>) -> bool,
     ) -> Vec<(Self, EdgeKind), NEIGHBOR_MAX> {
         let mut succs = Vec::new();
-        self.succs_flow(
+        let _ = self.succs_flow(
             |dx, dy| {
                 if let Some(coord) = self.coord.new_relative(dx, dy) {
                     let coord = match (coord.x & 1, coord.y & 1) {
@@ -207,12 +207,12 @@ impl<const W: u8> Node<W> {
             (0, 0, North | East | South | West) => {
                 cont(0, 1)?;
                 // straight
-                (1..).try_for_each(|i| {
+                let _ = (1..).try_for_each(|i| {
                     cont(0, 2 * i - 1)?;
                     add(0, 2 * i, North, Straight(i as u8))
                 });
                 // right
-                (|| {
+                let _ = (|| {
                     cont(1, 2)?;
                     add(1, 2, NorthEast, Slalom45)?;
                     add(2, 2, East, Slalom90)?;
@@ -221,7 +221,7 @@ impl<const W: u8> Node<W> {
                     add(2, 0, South, Slalom180)
                 })();
                 // left
-                (|| {
+                let _ = (|| {
                     cont(-1, 2)?;
                     add(-1, 2, NorthWest, Slalom45)?;
                     add(-2, 2, West, Slalom90)?;
@@ -229,14 +229,31 @@ impl<const W: u8> Node<W> {
                     add(-2, 1, SouthWest, Slalom135)?;
                     add(-2, 0, South, Slalom180)
                 })();
+                // s-turn right
+                let _ = (|| {
+                    cont(1, 2)?;
+                    cont(2, 3)?;
+                    add(2, 4, North, SlalomKojima)
+                })();
+                // s-turn left
+                let _ = (|| {
+                    cont(-1, 2)?;
+                    cont(-2, 3)?;
+                    add(-2, 4, North, SlalomKojima)
+                })();
             }
             // left
             (1, 0, NorthEast | SouthWest) | (0, 1, NorthWest | SouthEast) => {
                 cont(1, 1)?;
-                (1..).try_for_each(|i| {
+                let _ = (1..).try_for_each(|i| {
                     cont(i, i)?;
                     add(i, i, NorthEast, StraightDiagonal(i as u8))
                 });
+                let _ = (|| {
+                    cont(1, 3)?;
+                    cont(0, 4)?;
+                    add(0, 4, NorthWest, SlalomDiagonal90Large)
+                })();
                 add(1, 2, North, Slalom45)?;
                 cont(0, 2)?;
                 add(0, 2, NorthWest, SlalomDiagonal90)?;
@@ -245,10 +262,15 @@ impl<const W: u8> Node<W> {
             // right
             (0, 1, NorthEast | SouthWest) | (1, 0, NorthWest | SouthEast) => {
                 cont(1, 1)?;
-                (1..).try_for_each(|i| {
+                let _ = (1..).try_for_each(|i| {
                     cont(i, i)?;
                     add(i, i, NorthEast, StraightDiagonal(i as u8))
                 });
+                let _ = (|| {
+                    cont(3, 1)?;
+                    cont(4, 0)?;
+                    add(4, 0, SouthEast, SlalomDiagonal90Large)
+                })();
                 add(2, 1, East, Slalom45)?;
                 cont(2, 0)?;
                 add(2, 0, SouthEast, SlalomDiagonal90)?;
@@ -292,6 +314,8 @@ impl<const W: u8> Node<W> {
                 (-2, 2, West) => Slalom(FastRun90, Left),
                 (-2, 1, SouthWest) => Slalom(FastRun135, Left),
                 (-2, 0, South) => Slalom(FastRun180, Left),
+                (2, 4, North) => Slalom(FastRunKojima, Right),
+                (-2, 4, North) => Slalom(FastRunKojima, Left),
                 _ => return None,
             },
             //left
@@ -299,6 +323,7 @@ impl<const W: u8> Node<W> {
                 (x, y, NorthEast) if x == y && x > 0 => StraightDiagonal(x as u8),
                 (1, 2, North) => Slalom(FastRun45Rev, Left),
                 (0, 2, NorthWest) => Slalom(FastRunDiagonal90, Left),
+                (0, 4, NorthWest) => Slalom(FastRunDiagonal90Large, Left),
                 (-1, 2, West) => Slalom(FastRun135Rev, Left),
                 _ => return None,
             },
@@ -307,6 +332,7 @@ impl<const W: u8> Node<W> {
                 (x, y, NorthEast) if x == y && x > 0 => StraightDiagonal(x as u8),
                 (2, 1, East) => Slalom(FastRun45Rev, Right),
                 (2, 0, SouthEast) => Slalom(FastRunDiagonal90, Right),
+                (4, 0, SouthEast) => Slalom(FastRunDiagonal90Large, Right),
                 (2, -1, South) => Slalom(FastRun135Rev, Right),
                 _ => return None,
             },
@@ -331,6 +357,8 @@ pub enum EdgeKind {
     Slalom135,
     Slalom180,
     SlalomDiagonal90,
+    SlalomDiagonal90Large,
+    SlalomKojima,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, PartialOrd, Ord)]
@@ -383,6 +411,20 @@ impl<const W: u8> From<NodeId<W>> for Node<W> {
     }
 }
 
+impl<T, const W: u8> core::ops::Index<NodeId<W>> for [T] {
+    type Output = T;
+
+    fn index(&self, index: NodeId<W>) -> &Self::Output {
+        &(self)[index.as_index()]
+    }
+}
+
+impl<T, const W: u8> core::ops::IndexMut<NodeId<W>> for [T] {
+    fn index_mut(&mut self, index: NodeId<W>) -> &mut Self::Output {
+        &mut (self)[index.as_index()]
+    }
+}
+
 pub fn shortest_path<C, const W: u8>(
     start: Node<W>,
     is_goal: impl Fn(&Node<W>) -> bool,
@@ -417,20 +459,6 @@ where
         }
     }
 
-    impl<T, const W: u8> core::ops::Index<NodeId<W>> for [T] {
-        type Output = T;
-
-        fn index(&self, index: NodeId<W>) -> &Self::Output {
-            &(self)[index.as_index()]
-        }
-    }
-
-    impl<T, const W: u8> core::ops::IndexMut<NodeId<W>> for [T] {
-        fn index_mut(&mut self, index: NodeId<W>) -> &mut Self::Output {
-            &mut (self)[index.as_index()]
-        }
-    }
-
     let start = NodeId::from(start);
 
     let mut dist = [C::max_value(); QUE_MAX];
@@ -501,11 +529,11 @@ mod tests {
         let walls = walls.parse::<Walls<W>>().unwrap();
         let start = new_node((0, 0, Posture::North));
         let goals = goals
-            .into_iter()
+            .iter()
             .map(|&node| new_node(node))
             .collect::<Vec<_, 4>>();
         let expected = expected
-            .into_iter()
+            .iter()
             .map(|&node| new_node::<W>(node))
             .collect::<Vec<_, PATH_MAX>>();
 
@@ -514,7 +542,7 @@ mod tests {
             |node| goals.iter().any(|goal| node == goal),
             |coord| {
                 matches!(
-                    walls.wall_state(&coord),
+                    walls.wall_state(coord),
                     WallState::Checked { exists: true } | WallState::Unchecked
                 )
             },
@@ -526,6 +554,8 @@ mod tests {
                 Slalom135 => 20,
                 Slalom180 => 25,
                 SlalomDiagonal90 => 15,
+                SlalomDiagonal90Large => 20,
+                SlalomKojima => 22,
             },
         )
         .unwrap();
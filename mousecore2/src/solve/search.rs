@@ -3,6 +3,7 @@ use core::ops::ControlFlow;
 use heapless::{Deque, Vec};
 use serde::{Deserialize, Serialize};
 
+use crate::wall::{WallDetector, Walls};
 use crate::WIDTH;
 
 const FIL_LEN: usize = WIDTH * WIDTH / 4;
@@ -55,6 +56,11 @@ impl<const W: u8> Coordinate<W> {
         Some(Self { x, y })
     }
 
+    // Return all coordinates in the maze.
+    fn all() -> impl Iterator<Item = Self> {
+        (0..W << 1).flat_map(|y| (0..W << 1).filter_map(move |x| Self::new(x, y)))
+    }
+
     pub(crate) fn as_index(&self) -> usize {
         (((self.y as usize) >> 1) << (W.trailing_zeros() + 1))
             | (((self.x as usize) >> 1) << 1)
@@ -127,7 +133,7 @@ impl<const W: u8> Coordinate<W> {
         };
         macro_rules! add_seq {
             ($f: expr) => {
-                (1..).try_for_each($f);
+                let _ = (1..).try_for_each($f);
             };
         }
         add_seq!(|i| add_with_check(i, i, BottomLeftToTopRight));
@@ -163,7 +169,7 @@ impl<const W: u8> Coordinate<W> {
         };
         macro_rules! add_seq {
             ($f: expr) => {{
-                (1..).try_for_each($f);
+                let _ = (1..).try_for_each($f);
             }};
         }
 
@@ -267,14 +273,119 @@ pub enum WallState {
     Checked { exists: bool },
 }
 
+/// The maximum number of walls reported by [`SearchError`].
+pub const ISOLATING_MAX: usize = 16;
+
 /// Error on search.
-#[derive(Debug)]
-pub enum SearchError {
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchError<const W: u8> {
+    /// All candidates of a commander have turned out to be blocked.
+    Blocked,
+    /// No goal is reachable.
+    ///
+    /// `walls` are checked walls which separate the goals from the start or the robot.
+    GoalIsolated {
+        walls: Vec<Coordinate<W>, ISOLATING_MAX>,
+    },
+    /// The robot cannot reach any wall which should be checked.
+    ///
+    /// `walls` are checked walls which separate the robot from the start.
+    Unreachable {
+        walls: Vec<Coordinate<W>, ISOLATING_MAX>,
+    },
+}
+
+impl<const W: u8> SearchError<W> {
+    pub fn kind(&self) -> SearchErrorKind {
+        match self {
+            Self::Blocked => SearchErrorKind::Blocked,
+            Self::GoalIsolated { .. } => SearchErrorKind::GoalIsolated,
+            Self::Unreachable { .. } => SearchErrorKind::Unreachable,
+        }
+    }
+
+    /// Returns the walls reported by this error.
+    pub fn walls(&self) -> &[Coordinate<W>] {
+        match self {
+            Self::Blocked => &[],
+            Self::GoalIsolated { walls } | Self::Unreachable { walls } => walls,
+        }
+    }
+}
+
+/// The kind of [`SearchError`] without the reported walls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchErrorKind {
+    Blocked,
+    GoalIsolated,
     Unreachable,
 }
 
 const QUE_MAX: usize = WIDTH * WIDTH * 2;
 
+// Return walls reachable from walls satisfying `is_seed` without passing existing walls.
+fn region<const W: u8>(
+    is_seed: impl Fn(&Coordinate<W>) -> bool,
+    wall_state: impl Fn(&Coordinate<W>) -> WallState + Copy,
+) -> Filter {
+    let mut filter = Filter::new();
+    let mut que = Deque::<_, QUE_MAX>::new();
+    for coord in Coordinate::<W>::all() {
+        if is_seed(&coord) && wall_state(&coord) != (WallState::Checked { exists: true }) {
+            filter.set(&coord);
+            que.push_back(coord).unwrap();
+        }
+    }
+    while let Some(node) = que.pop_front() {
+        for next in node.neighbors(wall_state) {
+            if !filter.contains(&next) {
+                filter.set(&next);
+                que.push_back(next).unwrap();
+            }
+        }
+    }
+    filter
+}
+
+/// Returns existing walls which separate walls satisfying `is_from` from walls satisfying `is_to`.
+///
+/// At most [`ISOLATING_MAX`] walls are returned in descending order of `rank`, so that the walls
+/// most likely to be misdetected are kept, e.g. by [`WallDetector::checked_order`].
+pub fn isolating_walls<const W: u8>(
+    is_from: impl Fn(&Coordinate<W>) -> bool,
+    is_to: impl Fn(&Coordinate<W>) -> bool,
+    wall_state: impl Fn(&Coordinate<W>) -> WallState + Copy,
+    rank: impl Fn(&Coordinate<W>) -> u16,
+) -> Vec<Coordinate<W>, ISOLATING_MAX> {
+    let from = region(is_from, wall_state);
+    let to = region(is_to, wall_state);
+    let mut walls = Vec::<(u16, Coordinate<W>), ISOLATING_MAX>::new();
+    for coord in Coordinate::<W>::all()
+        .filter(|coord| wall_state(coord) == WallState::Checked { exists: true })
+        .filter(|coord| {
+            let neighbors = coord.neighbors(|_| WallState::Unchecked);
+            neighbors.iter().any(|coord| from.contains(coord))
+                && neighbors.iter().any(|coord| to.contains(coord))
+        })
+    {
+        let rank = rank(&coord);
+        let index = walls
+            .iter()
+            .position(|&(other, _)| other < rank)
+            .unwrap_or(walls.len());
+        if index == ISOLATING_MAX {
+            continue;
+        }
+        if walls.is_full() {
+            walls.pop();
+        }
+        walls
+            .insert(index, (rank, coord))
+            .expect("Should never fail: a wall has been removed if full.");
+    }
+    walls.into_iter().map(|(_, coord)| coord).collect()
+}
+
 impl<T, const W: u8> core::ops::Index<Coordinate<W>> for [T] {
     type Output = T;
 
@@ -422,10 +533,28 @@ impl<const W: u8> Searcher<W> {
         &self,
         current: &Coordinate<W>,
         wall_state: impl Fn(&Coordinate<W>) -> WallState + Copy,
-    ) -> Result<Option<Commander<W>>, SearchError> {
+    ) -> Result<Option<Commander<W>>, SearchError<W>> {
+        self.search_with_rank(current, wall_state, |_| 0)
+    }
+
+    /// Same as [`search`](Self::search), but the walls in the error are chosen by `rank`
+    /// as in [`isolating_walls`].
+    pub fn search_with_rank(
+        &self,
+        current: &Coordinate<W>,
+        wall_state: impl Fn(&Coordinate<W>) -> WallState + Copy,
+        rank: impl Fn(&Coordinate<W>) -> u16 + Copy,
+    ) -> Result<Option<Commander<W>>, SearchError<W>> {
         let (prev, goal) = self
             .bfs_tree(|coord| wall_state(coord) == WallState::Checked { exists: true })
-            .ok_or(SearchError::Unreachable)?;
+            .ok_or_else(|| SearchError::GoalIsolated {
+                walls: isolating_walls(
+                    |coord| coord == &self.start,
+                    |coord| self.goal_filter.contains(coord),
+                    wall_state,
+                    rank,
+                ),
+            })?;
 
         let walls_filter = self.unchecked_walls(wall_state, &prev, goal);
 
@@ -438,7 +567,14 @@ impl<const W: u8> Searcher<W> {
 
         let candidates =
             Self::candidates(current, wall_state, |coord| walls_filter.contains(coord))
-                .ok_or(SearchError::Unreachable)?;
+                .ok_or_else(|| SearchError::Unreachable {
+                    walls: isolating_walls(
+                        |coord| coord == current,
+                        |coord| coord == &self.start,
+                        wall_state,
+                        rank,
+                    ),
+                })?;
 
         Ok(Some(Commander { candidates }))
     }
@@ -453,7 +589,7 @@ impl<const W: u8> Commander<W> {
     pub fn next_coordinate(
         &self,
         wall_state: impl Fn(&Coordinate<W>) -> WallState,
-    ) -> Result<Option<Coordinate<W>>, SearchError> {
+    ) -> Result<Option<Coordinate<W>>, SearchError<W>> {
         for coord in &self.candidates {
             match wall_state(coord) {
                 WallState::Checked { exists: false } => return Ok(Some(*coord)),
@@ -461,7 +597,7 @@ impl<const W: u8> Commander<W> {
                 _ => (),
             }
         }
-        Err(SearchError::Unreachable)
+        Err(SearchError::Blocked)
     }
 
     pub fn force_next_coordinate(
@@ -475,7 +611,7 @@ impl<const W: u8> Commander<W> {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Posture {
     North,
     East,
@@ -483,7 +619,7 @@ pub enum Posture {
     West,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum TrajectoryKind {
     Front,
     Right,
@@ -491,7 +627,7 @@ pub enum TrajectoryKind {
     Back,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct SearchState<const W: u8> {
     coord: Coordinate<W>,
     posture: Posture,
@@ -575,6 +711,367 @@ impl<const W: u8> SearchState<W> {
     }
 }
 
+/// A phase of search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SearchPhase {
+    /// Exploring walls which may be on the shortest path.
+    Explore,
+    /// Heading for one of the goals.
+    ToGoal,
+    /// Coming back to the start.
+    Return,
+}
+
+/// A strategy which decides where the robot should go next during search.
+pub trait SearchStrategy<const W: u8> {
+    /// Returns a [`Commander`] for the next move, or `None` if the search has finished.
+    ///
+    /// `rank` chooses the walls reported in the error as in [`isolating_walls`].
+    fn next_commander(
+        &mut self,
+        state: &SearchState<W>,
+        wall_state: impl Fn(&Coordinate<W>) -> WallState + Copy,
+        rank: impl Fn(&Coordinate<W>) -> u16 + Copy,
+    ) -> Result<Option<Commander<W>>, SearchError<W>>;
+
+    /// Returns the current phase of the strategy.
+    fn phase(&self) -> SearchPhase {
+        SearchPhase::Explore
+    }
+}
+
+impl<const W: u8> SearchStrategy<W> for Searcher<W> {
+    fn next_commander(
+        &mut self,
+        state: &SearchState<W>,
+        wall_state: impl Fn(&Coordinate<W>) -> WallState + Copy,
+        rank: impl Fn(&Coordinate<W>) -> u16 + Copy,
+    ) -> Result<Option<Commander<W>>, SearchError<W>> {
+        self.search_with_rank(&state.coordinate(), wall_state, rank)
+    }
+}
+
+impl<const W: u8> SearchState<W> {
+    // Return the coordinate relative to `self`.
+    //
+    // `dx` is the offset to the right and `dy` is the offset to the front.
+    fn relative(&self, dx: i8, dy: i8) -> Option<Coordinate<W>> {
+        use Posture::*;
+
+        let (dx, dy) = match self.posture {
+            North => (dx, dy),
+            East => (dy, -dx),
+            South => (-dx, -dy),
+            West => (-dy, dx),
+        };
+        self.coord.new_relative(dx, dy)
+    }
+}
+
+/// A classic flood-fill searcher known as the Adachi method.
+///
+/// The robot goes down the distance map from goals, where unchecked walls are regarded as absent,
+/// and comes back to the start after reaching one of the goals.
+pub struct AdachiSearcher<const W: u8> {
+    start: Coordinate<W>,
+    goal_filter: Filter,
+    phase: SearchPhase,
+}
+
+impl<const W: u8> AdachiSearcher<W> {
+    pub fn new(start: Coordinate<W>, goals: &[Coordinate<W>]) -> Self {
+        Self {
+            start,
+            goal_filter: Filter::with_coord(goals),
+            phase: SearchPhase::ToGoal,
+        }
+    }
+
+    /// Creates a searcher which only goes back to the start.
+    pub fn homing(start: Coordinate<W>) -> Self {
+        Self {
+            start,
+            goal_filter: Filter::new(),
+            phase: SearchPhase::Return,
+        }
+    }
+
+    fn distances(&self, wall_state: impl Fn(&Coordinate<W>) -> WallState + Copy) -> [u16; QUE_MAX] {
+        let mut dist = [u16::MAX; QUE_MAX];
+        let mut que = Deque::<_, QUE_MAX>::new();
+        let mut push = |coord: Coordinate<W>, que: &mut Deque<_, QUE_MAX>| {
+            dist[coord] = 0;
+            que.push_back(coord).unwrap();
+        };
+        if self.phase == SearchPhase::Return {
+            push(self.start, &mut que);
+        } else {
+            Coordinate::all()
+                .filter(|goal| {
+                    self.goal_filter.contains(goal)
+                        && wall_state(goal) != WallState::Checked { exists: true }
+                })
+                .for_each(|goal| push(goal, &mut que));
+        }
+        while let Some(node) = que.pop_front() {
+            for next in node.neighbors(wall_state) {
+                if dist[next] != u16::MAX {
+                    continue;
+                }
+                dist[next] = dist[node] + 1;
+                que.push_back(next).unwrap();
+            }
+        }
+        dist
+    }
+}
+
+impl<const W: u8> SearchStrategy<W> for AdachiSearcher<W> {
+    fn next_commander(
+        &mut self,
+        state: &SearchState<W>,
+        wall_state: impl Fn(&Coordinate<W>) -> WallState + Copy,
+        rank: impl Fn(&Coordinate<W>) -> u16 + Copy,
+    ) -> Result<Option<Commander<W>>, SearchError<W>> {
+        let current = state.coordinate();
+        if self.phase == SearchPhase::ToGoal && self.goal_filter.contains(&current) {
+            self.phase = SearchPhase::Return;
+        }
+        if self.phase == SearchPhase::Return && current == self.start {
+            return Ok(None);
+        }
+
+        let dist = self.distances(wall_state);
+        let front = state.relative(0, 2);
+        let mut candidates = current
+            .neighbors(wall_state)
+            .into_iter()
+            .filter(|&coord| dist[coord] != u16::MAX)
+            .collect::<Vec<_, 6>>();
+        if candidates.is_empty() {
+            return Err(if self.phase == SearchPhase::Return {
+                SearchError::Unreachable {
+                    walls: isolating_walls(
+                        |coord| coord == &current,
+                        |coord| coord == &self.start,
+                        wall_state,
+                        rank,
+                    ),
+                }
+            } else {
+                SearchError::GoalIsolated {
+                    walls: isolating_walls(
+                        |coord| coord == &current,
+                        |coord| self.goal_filter.contains(coord),
+                        wall_state,
+                        rank,
+                    ),
+                }
+            });
+        }
+        // Prefer going straight among the nearest candidates.
+        candidates.sort_unstable_by_key(|&coord| (dist[coord], Some(coord) != front, coord));
+        Ok(Some(Commander { candidates }))
+    }
+
+    fn phase(&self) -> SearchPhase {
+        self.phase
+    }
+}
+
+/// A hand of [`HandSearcher`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+/// A wall follower which keeps its hand on the wall until it reaches one of the goals.
+///
+/// This searcher never reaches goals isolated from the outer walls, so it is only for testing.
+pub struct HandSearcher<const W: u8> {
+    hand: Hand,
+    goal_filter: Filter,
+}
+
+impl<const W: u8> HandSearcher<W> {
+    pub fn new(hand: Hand, goals: &[Coordinate<W>]) -> Self {
+        Self {
+            hand,
+            goal_filter: Filter::with_coord(goals),
+        }
+    }
+}
+
+impl<const W: u8> SearchStrategy<W> for HandSearcher<W> {
+    fn next_commander(
+        &mut self,
+        state: &SearchState<W>,
+        _wall_state: impl Fn(&Coordinate<W>) -> WallState + Copy,
+        _rank: impl Fn(&Coordinate<W>) -> u16 + Copy,
+    ) -> Result<Option<Commander<W>>, SearchError<W>> {
+        if self.goal_filter.contains(&state.coordinate()) {
+            return Ok(None);
+        }
+        let side = match self.hand {
+            Hand::Left => -1,
+            Hand::Right => 1,
+        };
+        let candidates = [
+            (side, 1),
+            (0, 2),
+            (-side, 1),
+            (0, -2),
+            (side, -1),
+            (-side, -1),
+        ]
+        .iter()
+        .filter_map(|&(dx, dy)| state.relative(dx, dy))
+        .collect::<Vec<_, 6>>();
+        Ok(Some(Commander { candidates }))
+    }
+
+    fn phase(&self) -> SearchPhase {
+        SearchPhase::ToGoal
+    }
+}
+
+/// A policy to recover from [`SearchError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryPolicy {
+    /// Check again the wall detected most recently among the walls in the error.
+    ReverifyLatest,
+    /// Check again the wall with the lowest confidence among the walls in the error.
+    ReverifyLeastConfident,
+    /// Check again all the walls in the error.
+    ReverifyAll,
+    /// Give up the search and go back to the start.
+    ReturnHome,
+}
+
+/// An action to recover from [`SearchError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecoveryAction<const W: u8> {
+    /// Request a new commander to the strategy.
+    Retry,
+    /// Check again the given walls and resume the search.
+    ///
+    /// [`apply`](Self::apply) resets them so that they are regarded as unchecked.
+    Reverify(Vec<Coordinate<W>, ISOLATING_MAX>),
+    /// Go back to the start, e.g. with [`AdachiSearcher::homing`].
+    ReturnHome,
+}
+
+impl<const W: u8> RecoveryAction<W> {
+    /// Resets walls to be checked again.
+    pub fn apply(&self, walls: &mut Walls<W>, detector: &mut WallDetector<W>) {
+        if let Self::Reverify(coords) = self {
+            for coord in coords {
+                walls.update(coord, &WallState::Unchecked);
+                detector.reset(coord);
+            }
+        }
+    }
+}
+
+/// The maximum number of policies of [`SearchRecovery`].
+pub const RECOVERY_POLICY_MAX: usize = 4;
+
+/// Error on creating [`SearchRecovery`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryPolicyError {
+    /// No policy is given.
+    Empty,
+    /// More than [`RECOVERY_POLICY_MAX`] policies are given.
+    TooMany,
+}
+
+/// Recovery from [`SearchError`] which escalates the policy every time it is invoked
+/// for the same error.
+///
+/// The last policy is used repeatedly after all policies have been tried.
+/// The escalation starts over when the error changes or [`reset`](Self::reset) is called,
+/// which should be done whenever the search makes progress.
+pub struct SearchRecovery<const W: u8> {
+    policies: Vec<RecoveryPolicy, RECOVERY_POLICY_MAX>,
+    count: usize,
+    last: Option<SearchError<W>>,
+}
+
+impl<const W: u8> Default for SearchRecovery<W> {
+    fn default() -> Self {
+        use RecoveryPolicy::*;
+
+        Self::new(&[
+            ReverifyLatest,
+            ReverifyLeastConfident,
+            ReverifyAll,
+            ReturnHome,
+        ])
+        .expect("Should never fail: the default policies are valid.")
+    }
+}
+
+impl<const W: u8> SearchRecovery<W> {
+    pub fn new(policies: &[RecoveryPolicy]) -> Result<Self, RecoveryPolicyError> {
+        if policies.is_empty() {
+            return Err(RecoveryPolicyError::Empty);
+        }
+        Ok(Self {
+            policies: Vec::from_slice(policies).map_err(|_| RecoveryPolicyError::TooMany)?,
+            count: 0,
+            last: None,
+        })
+    }
+
+    /// Starts the escalation over from the first policy.
+    pub fn reset(&mut self) {
+        self.count = 0;
+        self.last = None;
+    }
+
+    /// Returns an action to recover from the given error.
+    pub fn recover(
+        &mut self,
+        error: &SearchError<W>,
+        detector: &WallDetector<W>,
+    ) -> RecoveryAction<W> {
+        let walls = match error {
+            SearchError::Blocked => return RecoveryAction::Retry,
+            SearchError::GoalIsolated { walls } | SearchError::Unreachable { walls } => walls,
+        };
+        if self.last.as_ref() != Some(error) {
+            self.count = 0;
+            self.last = Some(error.clone());
+        }
+        let policy = self.policies[self.count.min(self.policies.len() - 1)];
+        self.count = self.count.saturating_add(1);
+
+        let latest = walls
+            .iter()
+            .max_by_key(|coord| detector.checked_order(coord));
+        let least_confident = walls.iter().min_by(|a, b| {
+            detector
+                .confidence(a)
+                .partial_cmp(&detector.confidence(b))
+                .unwrap_or(core::cmp::Ordering::Equal)
+        });
+        let reverify = |coord: Option<&Coordinate<W>>| {
+            coord.map_or(RecoveryAction::ReturnHome, |coord| {
+                RecoveryAction::Reverify(core::iter::once(*coord).collect())
+            })
+        };
+        match policy {
+            RecoveryPolicy::ReverifyLatest => reverify(latest),
+            RecoveryPolicy::ReverifyLeastConfident => reverify(least_confident),
+            RecoveryPolicy::ReverifyAll if !walls.is_empty() => {
+                RecoveryAction::Reverify(walls.clone())
+            }
+            RecoveryPolicy::ReverifyAll | RecoveryPolicy::ReturnHome => RecoveryAction::ReturnHome,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -607,7 +1104,7 @@ mod tests {
         const W: u8 = 4;
         for (start, goals, walls, expected) in test_cases {
             let start = new_coord(start);
-            let goals = goals.map(|goal| new_coord(goal));
+            let goals = goals.map(new_coord);
             let walls = walls.parse::<Walls<W>>().unwrap();
             let expected = expected.into_iter().map(new_coord::<W>).collect::<Vec<_>>();
             let searcher = Searcher::<W>::new(start, &goals);
@@ -636,7 +1133,7 @@ mod tests {
         const W: u8 = 4;
         for (start, goals, walls, unchecked_walls, expected) in test_cases {
             let start = new_coord(start);
-            let goals = goals.map(|goal| new_coord(goal));
+            let goals = goals.map(new_coord);
             let mut walls = walls.parse::<Walls<W>>().unwrap();
             for wall in unchecked_walls {
                 walls.update(&new_coord(wall), &WallState::Unchecked);
@@ -681,10 +1178,7 @@ mod tests {
         const W: u8 = 4;
         for (start, goals, walls, unchecked_walls, current, expected) in test_cases {
             let start = new_coord(start);
-            let goals = goals
-                .into_iter()
-                .map(|goal| new_coord(goal))
-                .collect::<Vec<_>>();
+            let goals = goals.into_iter().map(new_coord).collect::<Vec<_>>();
             let mut walls = walls.parse::<Walls<W>>().unwrap();
             for wall in unchecked_walls {
                 walls.update(&new_coord(wall), &WallState::Unchecked);
@@ -700,6 +1194,102 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_isolating_walls_rank() {
+        const W: u8 = 32;
+        // a vertical line of walls separates the left half from the right half.
+        let mut walls = Walls::<W>::new();
+        for y in (0..2 * W).step_by(2) {
+            walls.update(&new_coord((W - 1, y)), &WallState::Checked { exists: true });
+        }
+        let isolating = isolating_walls(
+            |coord| coord == &new_coord::<W>((0, 1)),
+            |coord| coord == &new_coord::<W>((2 * W - 2, 1)),
+            |coord| walls.wall_state(coord),
+            |coord| coord.y as u16,
+        );
+        let expected = (0..ISOLATING_MAX as u8)
+            .map(|i| new_coord((W - 1, 2 * W - 2 - 2 * i)))
+            .collect::<Vec<_>>();
+        assert_eq!(isolating.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn test_recovery_policies() {
+        assert_eq!(
+            SearchRecovery::<4>::new(&[]).err(),
+            Some(RecoveryPolicyError::Empty)
+        );
+        assert_eq!(
+            SearchRecovery::<4>::new(&[RecoveryPolicy::ReturnHome; RECOVERY_POLICY_MAX + 1]).err(),
+            Some(RecoveryPolicyError::TooMany)
+        );
+    }
+
+    #[test]
+    fn test_recover_from_isolated_goal() {
+        const W: u8 = 4;
+        let start = new_coord((0, 1));
+        let goals = [new_coord((2, 1)), new_coord((3, 0))];
+        let mut walls = include_str!("../../mazes/maze4_1.dat")
+            .parse::<Walls<W>>()
+            .unwrap();
+        // misdetected wall
+        walls.update(&new_coord((2, 3)), &WallState::Checked { exists: true });
+        let mut detector = WallDetector::with_walls(&walls);
+        let searcher = Searcher::<W>::new(start, &goals);
+
+        let error = searcher
+            .search(&new_coord((0, 3)), |coord| walls.wall_state(coord))
+            .unwrap_err();
+        let mut expected = [(1, 0), (5, 0), (4, 1), (1, 2), (3, 2), (2, 3)].map(new_coord::<W>);
+        expected.sort();
+        match &error {
+            SearchError::GoalIsolated { walls } => {
+                let mut walls = walls.clone();
+                walls.sort();
+                assert_eq!(walls.as_slice(), expected.as_slice());
+            }
+            _ => unreachable!("{:?}", error),
+        }
+
+        let mut recovery =
+            SearchRecovery::new(&[RecoveryPolicy::ReverifyAll, RecoveryPolicy::ReturnHome])
+                .unwrap();
+        assert_eq!(
+            recovery.recover(&SearchError::<W>::Blocked, &detector),
+            RecoveryAction::Retry
+        );
+        let action = recovery.recover(&error, &detector);
+        action.apply(&mut walls, &mut detector);
+        assert_eq!(walls.wall_state(&new_coord((2, 3))), WallState::Unchecked);
+        assert!(searcher
+            .search(&new_coord((0, 3)), |coord| walls.wall_state(coord))
+            .is_ok());
+        assert_eq!(
+            recovery.recover(&error, &detector),
+            RecoveryAction::ReturnHome
+        );
+
+        // the escalation starts over for another error or after progress.
+        let other = SearchError::Unreachable {
+            walls: core::iter::once(new_coord((1, 0))).collect(),
+        };
+        assert_eq!(
+            recovery.recover(&other, &detector),
+            RecoveryAction::Reverify(other.walls().iter().copied().collect())
+        );
+        assert_eq!(
+            recovery.recover(&other, &detector),
+            RecoveryAction::ReturnHome
+        );
+        recovery.reset();
+        assert_eq!(
+            recovery.recover(&other, &detector),
+            RecoveryAction::Reverify(other.walls().iter().copied().collect())
+        );
+    }
+
     #[test]
     fn test_extended_neighbors_corner1() {
         new_coord::<4>((0, 1)).extended_neighbors(|_| true, &[false, false, false]);
@@ -0,0 +1,303 @@
+use core::f32::consts::SQRT_2;
+
+use heapless::Vec;
+use uom::si::f32::{Acceleration, Jerk, Length, Time, Velocity};
+
+use crate::solve::run::{TrajectoryKind, PATH_MAX};
+use crate::trajectory::{
+    slalom::{SlalomConfig, SlalomKind},
+    straight::LengthStraightCalculatorGenerator,
+};
+
+/// Velocities at the start, the middle and the end of a trajectory.
+///
+/// For slaloms, `middle` is the velocity during the curve.
+/// For straights, `middle` is equal to `end`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VelocityPlan {
+    pub start: Velocity,
+    pub middle: Velocity,
+    pub end: Velocity,
+}
+
+/// A planner which assigns velocities to each trajectory of a fast run path.
+///
+/// Velocities are limited so that every slalom is entered at most at its maximum velocity
+/// and the robot has enough distance for braking before it.
+pub struct VelocityPlanner {
+    function_generator: LengthStraightCalculatorGenerator,
+    v_max: Velocity,
+    square_width: Length,
+}
+
+impl VelocityPlanner {
+    pub fn new(v_max: Velocity, a_max: Acceleration, j_max: Jerk, square_width: Length) -> Self {
+        Self {
+            function_generator: LengthStraightCalculatorGenerator::new(v_max, a_max, j_max),
+            v_max,
+            square_width,
+        }
+    }
+}
+
+impl VelocityPlanner {
+    pub fn plan(
+        &self,
+        kinds: &[TrajectoryKind],
+        config: &SlalomConfig,
+        v_start: Velocity,
+        v_end: Velocity,
+        max_velocity: impl Fn(&SlalomKind) -> Velocity,
+    ) -> Vec<VelocityPlan, PATH_MAX> {
+        let mut plans = kinds
+            .iter()
+            .map(|kind| VelocityPlan {
+                start: self.v_max,
+                middle: match kind {
+                    TrajectoryKind::Slalom(kind, _) => max_velocity(kind).min(self.v_max),
+                    _ => self.v_max,
+                },
+                end: self.v_max,
+            })
+            .collect::<Vec<_, PATH_MAX>>();
+
+        let mut v = v_start;
+        for (kind, plan) in kinds.iter().zip(plans.iter_mut()) {
+            let (l_start, l_end) = self.straight_lengths(kind, config);
+            plan.start = plan.start.min(v);
+            plan.middle = plan.middle.min(self.accelerate(l_start, plan.start));
+            plan.end = plan.end.min(self.accelerate(l_end, plan.middle));
+            v = plan.end;
+        }
+
+        let mut v = v_end;
+        for (kind, plan) in kinds.iter().zip(plans.iter_mut()).rev() {
+            let (l_start, l_end) = self.straight_lengths(kind, config);
+            plan.end = plan.end.min(v);
+            plan.middle = plan.middle.min(self.accelerate(l_end, plan.end));
+            plan.start = plan.start.min(self.accelerate(l_start, plan.middle));
+            v = plan.start;
+        }
+        plans
+    }
+
+    /// Estimates the time to run through `kinds` with `plans` without generating trajectories.
+    pub fn estimate_time(
+        &self,
+        kinds: &[TrajectoryKind],
+        config: &SlalomConfig,
+        plans: &[VelocityPlan],
+    ) -> Time {
+        use crate::trajectory::slalom::SlalomDirection::Left;
+
+        let straight = |distance: Length, v_start, v_end| {
+            if distance.value <= 0.0 {
+                return Default::default();
+            }
+            let (_, t_end, _) = self.function_generator.generate_with_terminal_velocity(
+                Default::default(),
+                distance,
+                v_start,
+                v_end,
+            );
+            t_end
+        };
+        kinds
+            .iter()
+            .zip(plans.iter())
+            .map(|(kind, plan)| {
+                let (l_start, l_end) = self.straight_lengths(kind, config);
+                let curve = match kind {
+                    TrajectoryKind::Slalom(SlalomKind::FastRunKojima, _) => {
+                        2.0 * config
+                            .parameters(SlalomKind::FastRunKojima, Left)
+                            .curve_duration(plan.middle)
+                    }
+                    TrajectoryKind::Slalom(kind, _) => {
+                        config.parameters(*kind, Left).curve_duration(plan.middle)
+                    }
+                    _ => Default::default(),
+                };
+                straight(l_start, plan.start, plan.middle)
+                    + curve
+                    + straight(l_end, plan.middle, plan.end)
+            })
+            .fold(Default::default(), |sum, time| sum + time)
+    }
+
+    // Return lengths of straights before and after the middle of the given trajectory.
+    fn straight_lengths(&self, kind: &TrajectoryKind, config: &SlalomConfig) -> (Length, Length) {
+        use crate::trajectory::slalom::SlalomDirection::Left;
+
+        match kind {
+            TrajectoryKind::Straight(x) => (*x as f32 * self.square_width, Default::default()),
+            TrajectoryKind::StraightDiagonal(x) => {
+                (*x as f32 * self.square_width / SQRT_2, Default::default())
+            }
+            TrajectoryKind::Slalom(SlalomKind::FastRunKojima, _) => {
+                let params = config.parameters(SlalomKind::FastRunKojima, Left);
+                (params.l_start, params.l_start)
+            }
+            TrajectoryKind::Slalom(kind, _) => {
+                let params = config.parameters(*kind, Left);
+                (params.l_start, params.l_end)
+            }
+        }
+    }
+
+    // Return the maximum velocity reachable from `v_start` within `distance`.
+    fn accelerate(&self, distance: Length, v_start: Velocity) -> Velocity {
+        if v_start >= self.v_max {
+            return self.v_max;
+        }
+        self.function_generator
+            .calculate_reachable_velocity(v_start, self.v_max, distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trajectory::{
+        slalom::{SlalomDirection, SlalomGenerator},
+        straight::StraightGenerator,
+    };
+    use uom::si::{
+        acceleration::meter_per_second_squared, jerk::meter_per_second_cubed, length::meter,
+        time::second, velocity::meter_per_second,
+    };
+
+    #[test]
+    fn test_plan() {
+        use SlalomDirection::*;
+        use SlalomKind::*;
+        use TrajectoryKind::*;
+
+        let square_width = Length::new::<meter>(0.09);
+        let a_max = Acceleration::new::<meter_per_second_squared>(10.0);
+        let j_max = Jerk::new::<meter_per_second_cubed>(100.0);
+        let v_max = Velocity::new::<meter_per_second>(3.0);
+        let v_slalom = Velocity::new::<meter_per_second>(0.5);
+        let planner = VelocityPlanner::new(v_max, a_max, j_max, square_width);
+        let generator = LengthStraightCalculatorGenerator::new(v_max, a_max, j_max);
+        let config = SlalomConfig::new(square_width, Default::default());
+
+        let kinds = [
+            Straight(1),
+            Slalom(FastRun90, Right),
+            Straight(14),
+            Slalom(FastRun180, Left),
+            Straight(2),
+        ];
+        let plans = planner.plan(
+            &kinds,
+            &config,
+            Default::default(),
+            Default::default(),
+            |_| v_slalom,
+        );
+
+        assert_eq!(plans.len(), kinds.len());
+        assert_eq!(plans[0].start, Velocity::default());
+        assert_eq!(plans[4].end, Velocity::default());
+        for (kind, plan) in kinds.iter().zip(plans.iter()) {
+            let (l_start, l_end) = planner.straight_lengths(kind, &config);
+            if let Slalom(..) = kind {
+                assert!(plan.middle <= v_slalom);
+            }
+            // every velocity can be reached from the previous one and braked to the next one.
+            let reachable = |distance, from: Velocity, to: Velocity| {
+                let v = generator.calculate_reachable_velocity(from, to, distance);
+                if from < to {
+                    to <= v + Velocity::new::<meter_per_second>(1e-3)
+                } else {
+                    to + Velocity::new::<meter_per_second>(1e-3) >= v
+                }
+            };
+            assert!(reachable(l_start, plan.start, plan.middle), "{:?}", plan);
+            assert!(reachable(l_end, plan.middle, plan.end), "{:?}", plan);
+        }
+        for pair in plans.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+        // the long straight must accelerate beyond the slalom velocity.
+        let straight = generator.calculate_reachable_velocity(
+            plans[2].start,
+            v_max,
+            14.0 * square_width / 2.0,
+        );
+        assert!(straight > v_slalom);
+    }
+
+    #[test]
+    fn test_estimate_time() {
+        use SlalomDirection::*;
+        use SlalomKind::*;
+        use TrajectoryKind::*;
+
+        let square_width = Length::new::<meter>(0.09);
+        let period = Time::new::<second>(0.001);
+        let a_max = Acceleration::new::<meter_per_second_squared>(10.0);
+        let j_max = Jerk::new::<meter_per_second_cubed>(100.0);
+        let v_max = Velocity::new::<meter_per_second>(3.0);
+        let planner = VelocityPlanner::new(v_max, a_max, j_max, square_width);
+        let straight = StraightGenerator::new(v_max, a_max, j_max, period);
+        let slalom = SlalomGenerator::new(period, v_max, a_max, j_max);
+        let config = SlalomConfig::new(square_width, Default::default());
+
+        let kinds = [
+            Straight(3),
+            Slalom(FastRun45, Right),
+            StraightDiagonal(4),
+            Slalom(FastRun135Rev, Left),
+            Slalom(FastRunKojima, Right),
+            Straight(2),
+        ];
+        let plans = planner.plan(
+            &kinds,
+            &config,
+            Default::default(),
+            Default::default(),
+            |_| Velocity::new::<meter_per_second>(0.8),
+        );
+        let estimated = planner.estimate_time(&kinds, &config, &plans);
+
+        let mut samples = 0;
+        for (kind, plan) in kinds.iter().zip(plans.iter()) {
+            let (l_start, _) = planner.straight_lengths(kind, &config);
+            samples += match kind {
+                Straight(_) | StraightDiagonal(_) => {
+                    straight.generate(l_start, plan.start, plan.end).count()
+                }
+                Slalom(FastRunKojima, dir) => slalom
+                    .generate_s_slalom_with_terminal_velocity(
+                        config.parameters(FastRunKojima, *dir),
+                        plan.start,
+                        plan.middle,
+                        plan.end,
+                    )
+                    .0
+                    .count(),
+                Slalom(kind, dir) => slalom
+                    .generate_slalom_with_terminal_velocity(
+                        config.parameters(*kind, *dir),
+                        plan.start,
+                        plan.middle,
+                        plan.end,
+                    )
+                    .0
+                    .count(),
+            };
+        }
+        // each part of trajectories emits at most one extra sample.
+        let generated = samples as f32 * period.get::<second>();
+        let estimated = estimated.get::<second>();
+        assert!(generated >= estimated, "{} {}", generated, estimated);
+        assert!(
+            generated <= estimated + 20.0 * period.get::<second>(),
+            "{} {}",
+            generated,
+            estimated
+        );
+    }
+}
@@ -1,59 +1,169 @@
+pub mod clothoid;
+pub mod feasibility;
+pub mod sequence;
 pub mod slalom;
 pub mod spin;
 pub mod straight;
 
-use core::marker::PhantomData;
-
+use heapless::Vec;
 #[allow(unused_imports)]
 use micromath::F32Ext;
-use typenum::{consts::*, operator_aliases::PartialQuot, type_operators::PartialDiv, Integer};
-use uom::marker::Div;
-use uom::si::f32::Time;
-use uom::si::{Dimension, Quantity, ISQ, SI};
+use uom::si::{
+    acceleration::meter_per_second_squared,
+    angular_acceleration::radian_per_second_squared,
+    angular_jerk::radian_per_second_cubed,
+    f32::{Acceleration, AngularAcceleration, AngularJerk, Jerk, Time, Velocity},
+    jerk::meter_per_second_cubed,
+};
 
 use crate::{
     control::{AngleTarget, LengthTarget, Target},
+    trajectory::feasibility::ScaleError,
     wall::Pose,
 };
 
-#[allow(clippy::type_complexity)]
-fn sqrt<D>(
-    val: Quantity<D, SI<f32>, f32>,
-) -> Quantity<
-    ISQ<
-        PartialQuot<D::L, P2>,
-        PartialQuot<D::M, P2>,
-        PartialQuot<D::T, P2>,
-        PartialQuot<D::I, P2>,
-        PartialQuot<D::Th, P2>,
-        PartialQuot<D::N, P2>,
-        PartialQuot<D::J, P2>,
-    >,
-    SI<f32>,
-    f32,
->
+/// A trajectory which can be evaluated at an arbitrary time.
+///
+/// Iterators emit targets every control period from `t = 0` while `t` does not exceed
+/// [`duration`](Self::duration).
+pub trait TimedTrajectory {
+    /// Returns the time from the start to the end of the trajectory.
+    fn duration(&self) -> Time;
+
+    /// Returns the target at `t` from the start.
+    ///
+    /// `t` is clamped into the trajectory, so this never depends on the progress of
+    /// the iteration.
+    fn evaluate(&self, t: Time) -> Target;
+
+    /// Moves the iteration so that the next target is the one at `t`.
+    fn seek(&mut self, t: Time);
+
+    /// Returns the time of the target which the iteration emits next.
+    fn next_time(&self) -> Time;
+
+    /// Returns the pose at the end of the trajectory.
+    fn end_pose(&self) -> Pose {
+        let target = self.evaluate(self.duration());
+        Pose {
+            x: target.x.x,
+            y: target.y.x,
+            theta: target.theta.x,
+        }
+    }
+}
+
+/// A summary of a trajectory which generators compute without generating it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TrajectorySummary {
+    pub duration: Time,
+    /// The translational velocity at the end.
+    pub end_velocity: Velocity,
+    /// The maximum translational velocity.
+    pub peak_velocity: Velocity,
+    /// The pose at the end in the frame of the start pose.
+    pub end_pose: Pose,
+}
+
+// The number of intervals of Gauss-Legendre quadrature in `integrate_heading`.
+const HEADING_INTEGRAL_COUNT: usize = 16;
+
+// Return the integrals of cosine and sine of `angle` over `[0, end]`.
+pub(crate) fn integrate_heading(angle: impl Fn(f32) -> f32, end: f32) -> (f32, f32) {
+    //gauss legendre (s=3)
+    let cs = [0.112701654, 0.5, 0.88729835];
+    let bs = [0.2777778, 0.44444445, 0.2777778];
+    let h = end / HEADING_INTEGRAL_COUNT as f32;
+    let mut x = 0.0;
+    let mut y = 0.0;
+    for i in 0..HEADING_INTEGRAL_COUNT {
+        for (c, b) in cs.iter().zip(bs.iter()) {
+            let angle = angle(h * (i as f32 + c));
+            x += b * h * angle.cos();
+            y += b * h * angle.sin();
+        }
+    }
+    (x, y)
+}
+
+/// A trajectory which follows `first` and then `second`.
+///
+/// Unlike [`core::iter::Chain`], this keeps both trajectories to implement [`TimedTrajectory`].
+#[derive(Clone)]
+pub struct ChainTrajectory<A, B> {
+    first: A,
+    second: B,
+    first_done: bool,
+}
+
+impl<A, B> ChainTrajectory<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first,
+            second,
+            first_done: false,
+        }
+    }
+}
+
+impl<A, B> Iterator for ChainTrajectory<A, B>
 where
-    D: Dimension + ?Sized,
-    D::L: PartialDiv<P2>,
-    <D::L as PartialDiv<P2>>::Output: Integer,
-    D::M: PartialDiv<P2>,
-    <D::M as PartialDiv<P2>>::Output: Integer,
-    D::T: PartialDiv<P2>,
-    <D::T as PartialDiv<P2>>::Output: Integer,
-    D::I: PartialDiv<P2>,
-    <D::I as PartialDiv<P2>>::Output: Integer,
-    D::Th: PartialDiv<P2>,
-    <D::Th as PartialDiv<P2>>::Output: Integer,
-    D::N: PartialDiv<P2>,
-    <D::N as PartialDiv<P2>>::Output: Integer,
-    D::J: PartialDiv<P2>,
-    <D::J as PartialDiv<P2>>::Output: Integer,
-    D::Kind: Div,
+    A: TimedTrajectory + Iterator<Item = Target>,
+    B: TimedTrajectory + Iterator<Item = Target>,
 {
-    Quantity {
-        dimension: PhantomData,
-        units: PhantomData,
-        value: val.value.sqrt(),
+    type Item = Target;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.first_done {
+            if let Some(target) = self.first.next() {
+                return Some(target);
+            }
+            self.first_done = true;
+            // The second starts one period after the last target of the first,
+            // which keeps the iteration on the same time base as `evaluate`.
+            self.second
+                .seek(self.first.next_time() - self.first.duration());
+        }
+        self.second.next()
+    }
+}
+
+impl<A, B> TimedTrajectory for ChainTrajectory<A, B>
+where
+    A: TimedTrajectory,
+    B: TimedTrajectory,
+{
+    fn duration(&self) -> Time {
+        self.first.duration() + self.second.duration()
+    }
+
+    fn evaluate(&self, t: Time) -> Target {
+        let first = self.first.duration();
+        if t <= first {
+            self.first.evaluate(t)
+        } else {
+            self.second.evaluate(t - first)
+        }
+    }
+
+    fn seek(&mut self, t: Time) {
+        let first = self.first.duration();
+        if t <= first {
+            self.first.seek(t);
+            self.second.seek(Default::default());
+            self.first_done = false;
+        } else {
+            self.second.seek(t - first);
+            self.first_done = true;
+        }
+    }
+
+    fn next_time(&self) -> Time {
+        if self.first_done {
+            self.first.duration() + self.second.next_time()
+        } else {
+            self.first.next_time()
+        }
     }
 }
 
@@ -116,10 +226,212 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         self.inner.next().map(|item| self.shift(item))
     }
+}
+
+impl<T> TimedTrajectory for ShiftTrajectory<T>
+where
+    T: TimedTrajectory,
+{
+    fn duration(&self) -> Time {
+        self.inner.duration()
+    }
+
+    fn evaluate(&self, t: Time) -> Target {
+        self.shift(self.inner.evaluate(t))
+    }
+
+    fn seek(&mut self, t: Time) {
+        self.inner.seek(t);
+    }
+
+    fn next_time(&self) -> Time {
+        self.inner.next_time()
+    }
+}
+
+// Return the target of a trajectory whose time runs `scale` times as fast,
+// where `dscale` is the derivative of `scale` with respect to the new time.
+pub(crate) fn scale_target(target: Target, scale: f32, dscale: f32) -> Target {
+    let ss = scale * scale;
+    let length = |target: LengthTarget| LengthTarget {
+        x: target.x,
+        v: target.v * scale,
+        a: Acceleration::new::<meter_per_second_squared>(
+            target.a.value * ss + target.v.value * dscale,
+        ),
+        j: Jerk::new::<meter_per_second_cubed>(
+            target.j.value * ss * scale + 3.0 * target.a.value * scale * dscale,
+        ),
+    };
+    Target {
+        x: length(target.x),
+        y: length(target.y),
+        theta: AngleTarget {
+            x: target.theta.x,
+            v: target.theta.v * scale,
+            a: AngularAcceleration::new::<radian_per_second_squared>(
+                target.theta.a.value * ss + target.theta.v.value * dscale,
+            ),
+            j: AngularJerk::new::<radian_per_second_cubed>(
+                target.theta.j.value * ss * scale + 3.0 * target.theta.a.value * scale * dscale,
+            ),
+        },
+    }
+}
+
+/// A trajectory which follows the same path as `inner` with time running `scale` times
+/// as fast.
+///
+/// A scale less than one slows the trajectory down; velocities are multiplied by the
+/// scale, accelerations by its square and jerks by its cube.
+#[derive(Clone)]
+pub struct TimeScaledTrajectory<T> {
+    inner: T,
+    scale: f32,
+    t: Time,
+    period: Time,
+}
+
+impl<T> TimeScaledTrajectory<T> {
+    pub fn new(inner: T, scale: f32, period: Time) -> Result<Self, ScaleError> {
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(ScaleError::InvalidScale);
+        }
+        Ok(Self {
+            inner,
+            scale,
+            t: Default::default(),
+            period,
+        })
+    }
+}
+
+impl<T> Iterator for TimeScaledTrajectory<T>
+where
+    T: TimedTrajectory,
+{
+    type Item = Target;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.t > self.duration() {
+            return None;
+        }
+        let target = self.evaluate(self.t);
+        self.t += self.period;
+        Some(target)
+    }
+}
+
+impl<T> TimedTrajectory for TimeScaledTrajectory<T>
+where
+    T: TimedTrajectory,
+{
+    fn duration(&self) -> Time {
+        self.inner.duration() / self.scale
+    }
+
+    fn evaluate(&self, t: Time) -> Target {
+        scale_target(self.inner.evaluate(t * self.scale), self.scale, 0.0)
+    }
+
+    fn seek(&mut self, t: Time) {
+        self.t = t;
+    }
+
+    fn next_time(&self) -> Time {
+        self.t
+    }
+}
+
+// Return the target at `ratio` of the way from `from` to `to`.
+fn interpolate(from: &Target, to: &Target, ratio: f32) -> Target {
+    let length = |from: LengthTarget, to: LengthTarget| LengthTarget {
+        x: from.x + (to.x - from.x) * ratio,
+        v: from.v + (to.v - from.v) * ratio,
+        a: from.a + (to.a - from.a) * ratio,
+        j: from.j + (to.j - from.j) * ratio,
+    };
+    Target {
+        x: length(from.x, to.x),
+        y: length(from.y, to.y),
+        theta: AngleTarget {
+            x: from.theta.x + (to.theta.x - from.theta.x) * ratio,
+            v: from.theta.v + (to.theta.v - from.theta.v) * ratio,
+            a: from.theta.a + (to.theta.a - from.theta.a) * ratio,
+            j: from.theta.j + (to.theta.j - from.theta.j) * ratio,
+        },
+    }
+}
+
+/// A trajectory made of targets emitted every `period` by a plain iterator.
+///
+/// This holds at most `N` targets and linearly interpolates between consecutive ones,
+/// so that [`TimeScaledTrajectory`] and
+/// [`FeasibilityAnalyzer::scale_locally`](feasibility::FeasibilityAnalyzer::scale_locally)
+/// can sample it at arbitrary times.
+#[derive(Clone)]
+pub struct SampledTrajectory<const N: usize> {
+    targets: Vec<Target, N>,
+    period: Time,
+    t: Time,
+}
+
+impl<const N: usize> SampledTrajectory<N> {
+    pub fn new(
+        targets: impl IntoIterator<Item = Target>,
+        period: Time,
+    ) -> Result<Self, ScaleError> {
+        let mut samples = Vec::new();
+        for target in targets {
+            samples
+                .push(target)
+                .map_err(|_| ScaleError::TooManyTargets)?;
+        }
+        if samples.is_empty() {
+            return Err(ScaleError::NoTargets);
+        }
+        Ok(Self {
+            targets: samples,
+            period,
+            t: Default::default(),
+        })
+    }
+}
+
+impl<const N: usize> Iterator for SampledTrajectory<N> {
+    type Item = Target;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.t > self.duration() {
+            return None;
+        }
+        let target = self.evaluate(self.t);
+        self.t += self.period;
+        Some(target)
+    }
+}
 
-    #[cfg(nightly)]
-    fn advance_by(&mut self, n: usize) -> Result<(), usize> {
-        self.inner.advance_by(n)
+impl<const N: usize> TimedTrajectory for SampledTrajectory<N> {
+    fn duration(&self) -> Time {
+        self.period * (self.targets.len() - 1) as f32
+    }
+
+    fn evaluate(&self, t: Time) -> Target {
+        let last = self.targets.len() - 1;
+        let position = (t / self.period).value.max(0.0).min(last as f32);
+        let index = (position as usize).min(last.saturating_sub(1));
+        match self.targets.get(index + 1) {
+            Some(next) => interpolate(&self.targets[index], next, position - index as f32),
+            None => self.targets[index],
+        }
+    }
+
+    fn seek(&mut self, t: Time) {
+        self.t = t;
+    }
+
+    fn next_time(&self) -> Time {
+        self.t
     }
 }
 
@@ -148,34 +460,42 @@ impl Iterator for StopTrajectory {
     fn next(&mut self) -> Option<Self::Item> {
         if self.t < self.t_end {
             self.t += self.period;
-            Some(Target {
-                x: LengthTarget {
-                    x: self.pose.x,
-                    ..Default::default()
-                },
-                y: LengthTarget {
-                    x: self.pose.y,
-                    ..Default::default()
-                },
-                theta: AngleTarget {
-                    x: self.pose.theta,
-                    ..Default::default()
-                },
-            })
+            Some(self.evaluate(self.t))
         } else {
             None
         }
     }
+}
 
-    #[cfg(nightly)]
-    fn advance_by(&mut self, n: usize) -> Result<(), usize> {
-        self.t += self.period * n as f32;
-        if self.t < self.t_end {
-            Ok(())
-        } else {
-            Err(n - ((self.t - self.t_end) / self.period).get::<uom::si::ratio::ratio>() as usize)
+impl TimedTrajectory for StopTrajectory {
+    fn duration(&self) -> Time {
+        self.t_end
+    }
+
+    fn evaluate(&self, _t: Time) -> Target {
+        Target {
+            x: LengthTarget {
+                x: self.pose.x,
+                ..Default::default()
+            },
+            y: LengthTarget {
+                x: self.pose.y,
+                ..Default::default()
+            },
+            theta: AngleTarget {
+                x: self.pose.theta,
+                ..Default::default()
+            },
         }
     }
+
+    fn seek(&mut self, t: Time) {
+        self.t = t;
+    }
+
+    fn next_time(&self) -> Time {
+        self.t
+    }
 }
 
 #[cfg(test)]
@@ -240,6 +560,130 @@ mod tests {
         assert_target_relative_eq(trajectory.next().unwrap(), expected_target);
     }
 
+    #[test]
+    fn test_evaluate_chain_trajectory() {
+        use crate::trajectory::straight::StraightGenerator;
+        use uom::si::{
+            f32::{Acceleration, Jerk},
+            time::second,
+        };
+
+        let period = Time::new::<second>(0.001);
+        let generator = StraightGenerator::new(
+            Velocity::new::<meter_per_second>(1.0),
+            Acceleration::new::<meter_per_second_squared>(5.0),
+            Jerk::new::<meter_per_second_cubed>(100.0),
+            period,
+        );
+        let distance = Length::new::<meter>(0.09);
+        let pose = Pose {
+            x: distance,
+            ..Default::default()
+        };
+        let trajectory = ChainTrajectory::new(
+            generator.generate(distance, Default::default(), Default::default()),
+            StopTrajectory::new(pose, period, Time::new::<second>(0.1)),
+        );
+        let straight = trajectory.first.duration();
+        assert_relative_eq!(
+            trajectory.duration().get::<second>(),
+            straight.get::<second>() + 0.1
+        );
+        assert_relative_eq!(trajectory.end_pose().x.get::<meter>(), 0.09);
+
+        let mut seeked = trajectory.clone();
+        seeked.seek(straight / 2.0);
+        assert_target_relative_eq(seeked.next().unwrap(), trajectory.evaluate(straight / 2.0));
+        seeked.seek(straight + Time::new::<second>(0.05));
+        assert_eq!(seeked.count(), 50);
+
+        // the iteration of chained straights follows `evaluate` across the joints.
+        let v = Velocity::new::<meter_per_second>(1.0);
+        let straight = || StraightGenerator::generate_constant(distance, v, period);
+        let shifted = |i: f32| {
+            ShiftTrajectory::new(
+                Pose {
+                    x: distance * i,
+                    ..pose
+                },
+                straight(),
+            )
+        };
+        let trajectory = ChainTrajectory::new(
+            ChainTrajectory::new(ChainTrajectory::new(straight(), shifted(1.0)), shifted(2.0)),
+            shifted(3.0),
+        );
+        let mut count = 0;
+        for (i, target) in trajectory.clone().enumerate() {
+            let expected = trajectory.evaluate(period * i as f32);
+            assert_relative_eq!(
+                target.x.x.get::<meter>(),
+                expected.x.x.get::<meter>(),
+                epsilon = 1e-5
+            );
+            count += 1;
+        }
+        assert_relative_eq!(
+            count as f32,
+            (trajectory.duration() / period).value + 1.0,
+            epsilon = 1.0
+        );
+    }
+
+    #[test]
+    fn test_sampled_trajectory() {
+        use crate::trajectory::straight::StraightGenerator;
+        use uom::si::time::second;
+
+        let period = Time::new::<second>(0.001);
+        let v = Velocity::new::<meter_per_second>(1.0);
+        let straight = StraightGenerator::generate_constant(Length::new::<meter>(0.09), v, period);
+        let count = straight.clone().count();
+        // `Take` is a plain iterator without `evaluate`.
+        let targets = || straight.clone().take(count);
+        let sampled = SampledTrajectory::<128>::new(targets(), period).unwrap();
+        assert_relative_eq!(
+            sampled.duration().get::<second>(),
+            period.get::<second>() * (count - 1) as f32
+        );
+        for (i, target) in targets().enumerate() {
+            assert_target_relative_eq(sampled.evaluate(period * i as f32), target);
+        }
+        assert_relative_eq!(
+            sampled.evaluate(period * 1.5).x.x.get::<meter>(),
+            (straight.evaluate(period).x.x + straight.evaluate(period * 2.0).x.x).get::<meter>()
+                / 2.0
+        );
+
+        let scaled = TimeScaledTrajectory::new(sampled.clone(), 0.5, period).unwrap();
+        assert_relative_eq!(
+            scaled.duration().get::<second>(),
+            sampled.duration().get::<second>() * 2.0
+        );
+        assert_relative_eq!(
+            scaled.end_pose().x.get::<meter>(),
+            sampled.end_pose().x.get::<meter>()
+        );
+        for target in scaled {
+            assert_relative_eq!(target.x.v.get::<meter_per_second>(), 0.5);
+        }
+
+        assert_eq!(
+            SampledTrajectory::<16>::new(targets(), period).err(),
+            Some(ScaleError::TooManyTargets)
+        );
+        assert_eq!(
+            SampledTrajectory::<16>::new(core::iter::empty(), period).err(),
+            Some(ScaleError::NoTargets)
+        );
+        for scale in [0.0, -1.0, f32::NAN] {
+            assert_eq!(
+                TimeScaledTrajectory::new(sampled.clone(), scale, period).err(),
+                Some(ScaleError::InvalidScale)
+            );
+        }
+    }
+
     fn assert_target_relative_eq(left: Target, right: Target) {
         assert_relative_eq!(left.x.x.get::<meter>(), right.x.x.get::<meter>());
         assert_relative_eq!(
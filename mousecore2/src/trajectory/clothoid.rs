@@ -0,0 +1,478 @@
+#[allow(unused_imports)]
+use micromath::F32Ext;
+use serde::{Deserialize, Serialize};
+use uom::si::{
+    f32::{Angle, AngularAcceleration, AngularJerk, AngularVelocity, Length, Time, Velocity},
+    ratio::ratio,
+};
+
+use crate::{
+    control::{AngleTarget, LengthTarget, Target},
+    trajectory::{
+        integrate_heading,
+        slalom::{SlalomError, SlalomParameters},
+        TimedTrajectory,
+    },
+    wall::Pose,
+};
+
+/// A turn which consists of an entry clothoid, an arc and an exit clothoid.
+///
+/// The curvature rises linearly along the entry clothoid, stays at `1 / radius` on the arc
+/// and falls linearly along the exit clothoid.
+/// A negative `theta` makes a clockwise turn.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ClothoidTurn {
+    pub theta: Angle,
+    pub radius: Length,
+    pub clothoid_length: Length,
+}
+
+impl ClothoidTurn {
+    /// Creates a turn with the given radius where clothoids make `clothoid_ratio` of `theta`.
+    pub fn new(theta: Angle, radius: Length, clothoid_ratio: f32) -> Self {
+        let clothoid_ratio = clothoid_ratio.clamp(0.0, 1.0);
+        Self {
+            theta,
+            radius,
+            clothoid_length: radius * theta.value.abs() * clothoid_ratio,
+        }
+    }
+
+    /// Returns the length of the whole curve.
+    pub fn length(&self) -> Length {
+        self.radius * self.theta.value.abs() + self.clothoid_length
+    }
+
+    // Return the angle, the curvature and the derivative of the curvature at `s`
+    // for the left turn with a unit radius.
+    fn unit_profile(&self, s: f32) -> (f32, f32, f32) {
+        let lc = (self.clothoid_length / self.radius).get::<ratio>();
+        let theta = self.theta.value.abs();
+        let total = theta + lc;
+        if s < lc {
+            (s * s / (2.0 * lc), s / lc, 1.0 / lc)
+        } else if s < total - lc {
+            (lc / 2.0 + s - lc, 1.0, 0.0)
+        } else {
+            let u = (total - s).max(0.0);
+            if lc > 0.0 {
+                (theta - u * u / (2.0 * lc), u / lc, -1.0 / lc)
+            } else {
+                (theta, 1.0, 0.0)
+            }
+        }
+    }
+
+    // Return the angle, the curvature and the derivative of the curvature at `s`.
+    fn profile(&self, s: Length) -> (Angle, f32, f32) {
+        let radius = self.radius.value;
+        let sign = self.theta.value.signum();
+        let (angle, curvature, dcurvature) = self.unit_profile(s.value / radius);
+        (
+            Angle::new::<uom::si::angle::radian>(sign * angle),
+            sign * curvature / radius,
+            sign * dcurvature / (radius * radius),
+        )
+    }
+
+    /// Returns the displacement from the start to the end of the curve
+    /// in the frame of the start pose.
+    pub fn displacement(&self) -> (Length, Length) {
+        let lc = (self.clothoid_length / self.radius).get::<ratio>();
+        let theta = self.theta.value.abs();
+        let total = theta + lc;
+        let mut x = 0.0;
+        let mut y = 0.0;
+        // integrate each segment separately since the angle is not smooth at their joints.
+        for (start, end) in [(0.0, lc), (lc, total - lc), (total - lc, total)] {
+            if end <= start {
+                continue;
+            }
+            let (dx, dy) = integrate_heading(|s| self.unit_profile(start + s).0, end - start);
+            x += dx;
+            y += dy;
+        }
+        let sign = self.theta.value.signum();
+        (self.radius * x, self.radius * y * sign)
+    }
+}
+
+/// A [`ClothoidTurn`] with straights before and after it.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ClothoidSlalom {
+    pub turn: ClothoidTurn,
+    pub l_start: Length,
+    pub l_end: Length,
+}
+
+impl ClothoidSlalom {
+    /// Solves the slalom which connects the origin facing along the x-axis with `end`.
+    ///
+    /// The radius is made as large as possible
+    /// while both straights are kept longer than `min_straight`.
+    /// Clothoids make `clothoid_ratio` of the turn angle.
+    pub fn solve(
+        end: &Pose,
+        clothoid_ratio: f32,
+        min_straight: Length,
+    ) -> Result<Self, SlalomError> {
+        let theta = end.theta.value;
+        let sign = theta.signum();
+        let (sin, cos) = (theta.abs().sin(), theta.abs().cos());
+        if theta.abs() < 1e-3 || theta.abs() > core::f32::consts::PI + 1e-3 {
+            return Err(SlalomError::InvalidAngle);
+        }
+        // solve the left turn and mirror it if needed.
+        let x = end.x - min_straight * (1.0 + cos);
+        let y = end.y * sign - min_straight * sin;
+
+        let unit = ClothoidTurn::new(
+            Angle::new::<uom::si::angle::radian>(theta.abs()),
+            Length::new::<uom::si::length::meter>(1.0),
+            clothoid_ratio,
+        );
+        let (dx, dy) = unit.displacement();
+        let (dx, dy) = (dx.value, dy.value);
+
+        let (radius, l_start, l_end) = if sin < 1e-3 {
+            // the lateral offset decides the radius of a U-turn.
+            let radius = y / dy;
+            let diff = x - radius * dx;
+            (
+                radius,
+                diff.max(Default::default()),
+                (-diff).max(Default::default()),
+            )
+        } else {
+            let cot = cos / sin;
+            // l_end = (y - radius * dy) / sin
+            // l_start = x - y * cot - radius * (dx - dy * cot)
+            let mut radius = y / dy;
+            let coef = dx - dy * cot;
+            if coef > 0.0 {
+                radius = radius.min((x - y * cot) / coef);
+            }
+            let l_end = (y - radius * dy) / sin;
+            let l_start = x - y * cot - radius * coef;
+            (radius, l_start, l_end)
+        };
+
+        let tolerance = Length::new::<uom::si::length::meter>(1e-6);
+        if radius.value <= 0.0 || l_start < -tolerance || l_end < -tolerance {
+            return Err(SlalomError::DoesNotFit);
+        }
+        Ok(Self {
+            turn: ClothoidTurn::new(end.theta, radius, clothoid_ratio),
+            l_start: l_start.max(Default::default()) + min_straight,
+            l_end: l_end.max(Default::default()) + min_straight,
+        })
+    }
+
+    /// Returns [`SlalomParameters`] which reproduce this slalom with
+    /// [`SlalomGenerator`](crate::trajectory::slalom::SlalomGenerator).
+    ///
+    /// The angular velocity and acceleration are chosen so that the curvature of the slalom
+    /// follows the clothoids at `v_ref`, so the clothoids must have a positive length.
+    /// A large `dddtheta` keeps the geometry close to the clothoids.
+    pub fn parameters(&self, v_ref: Velocity, dddtheta: AngularJerk) -> SlalomParameters {
+        use uom::si::{
+            angular_acceleration::radian_per_second_squared, angular_velocity::radian_per_second,
+        };
+
+        let radius = self.turn.radius.value;
+        let clothoid_length = self.turn.clothoid_length.value;
+        let (dx, dy) = self.turn.displacement();
+        let dtheta = AngularVelocity::new::<radian_per_second>(v_ref.value / radius);
+        let ddtheta = AngularAcceleration::new::<radian_per_second_squared>(
+            v_ref.value * v_ref.value / (radius * clothoid_length),
+        );
+        SlalomParameters {
+            l_start: self.l_start,
+            l_end: self.l_end,
+            x_curve_end: self.l_start + dx,
+            y_curve_end: dy,
+            theta: self.turn.theta,
+            v_ref,
+            dtheta,
+            ddtheta,
+            dddtheta,
+        }
+    }
+}
+
+/// A trajectory along a [`ClothoidTurn`] at a constant velocity.
+#[derive(Clone)]
+pub struct ClothoidTrajectory {
+    turn: ClothoidTurn,
+    t: Time,
+    t_end: Time,
+    period: Time,
+    x_start: Length,
+    x: Length,
+    y: Length,
+    v: Velocity,
+}
+
+impl ClothoidTrajectory {
+    pub fn new(turn: ClothoidTurn, x_start: Length, v: Velocity, period: Time) -> Self {
+        Self {
+            turn,
+            t: Default::default(),
+            t_end: turn.length() / v,
+            period,
+            x_start,
+            x: x_start,
+            y: Default::default(),
+            v,
+        }
+    }
+
+    fn position_at(&self, t: Time) -> (Length, Length) {
+        use uom::si::length::meter;
+
+        let t = if t > self.t_end { self.t_end } else { t };
+        let (dx, dy) = integrate_heading(
+            |s| self.turn.profile(Length::new::<meter>(s)).0.value,
+            (self.v * t).value.max(0.0),
+        );
+        (
+            self.x_start + Length::new::<meter>(dx),
+            Length::new::<meter>(dy),
+        )
+    }
+
+    fn target(&self, t: Time, x: Length, y: Length) -> Target {
+        let (angle, curvature, dcurvature) = self.turn.profile(self.v * t);
+        let sin_theta = angle.value.sin();
+        let cos_theta = angle.value.cos();
+        let omega = self.v.value * curvature;
+        let domega = self.v.value * self.v.value * dcurvature;
+        let v = self.v.value;
+        let theta = AngleTarget {
+            x: angle,
+            v: AngularVelocity::new::<uom::si::angular_velocity::radian_per_second>(omega),
+            a: AngularAcceleration::new::<uom::si::angular_acceleration::radian_per_second_squared>(
+                domega,
+            ),
+            j: Default::default(),
+        };
+        let length_target = |x, v: f32, a: f32, j: f32| {
+            use uom::si::{
+                acceleration::meter_per_second_squared, f32::Acceleration, f32::Jerk,
+                jerk::meter_per_second_cubed, velocity::meter_per_second,
+            };
+            LengthTarget {
+                x,
+                v: Velocity::new::<meter_per_second>(v),
+                a: Acceleration::new::<meter_per_second_squared>(a),
+                j: Jerk::new::<meter_per_second_cubed>(j),
+            }
+        };
+        Target {
+            x: length_target(
+                x,
+                v * cos_theta,
+                -v * sin_theta * omega,
+                -v * (cos_theta * omega * omega + sin_theta * domega),
+            ),
+            y: length_target(
+                y,
+                v * sin_theta,
+                v * cos_theta * omega,
+                v * (-sin_theta * omega * omega + cos_theta * domega),
+            ),
+            theta,
+        }
+    }
+}
+
+impl Iterator for ClothoidTrajectory {
+    type Item = Target;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.t > self.t_end {
+            return None;
+        }
+        let t = self.t;
+        self.t += self.period;
+
+        //gauss legendre (s=2)
+        let cs = [0.21132487, 0.7886751];
+        let mut delta_x = 0.0;
+        let mut delta_y = 0.0;
+        for c in cs {
+            let (angle, _, _) = self.turn.profile(self.v * (t + c * self.period));
+            delta_x += 0.5 * angle.value.cos();
+            delta_y += 0.5 * angle.value.sin();
+        }
+        let x = self.x;
+        let y = self.y;
+        self.x += self.v * self.period * delta_x;
+        self.y += self.v * self.period * delta_y;
+
+        Some(self.target(t, x, y))
+    }
+}
+
+impl TimedTrajectory for ClothoidTrajectory {
+    fn duration(&self) -> Time {
+        self.t_end
+    }
+
+    fn evaluate(&self, t: Time) -> Target {
+        let (x, y) = self.position_at(t);
+        self.target(t, x, y)
+    }
+
+    fn seek(&mut self, t: Time) {
+        self.t = t;
+        let (x, y) = self.position_at(t);
+        self.x = x;
+        self.y = y;
+    }
+
+    fn next_time(&self) -> Time {
+        self.t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use core::f32::consts::SQRT_2;
+    use uom::si::{
+        acceleration::meter_per_second_squared, angle::degree,
+        angular_jerk::radian_per_second_cubed, jerk::meter_per_second_cubed, length::meter,
+        time::second, velocity::meter_per_second,
+    };
+
+    use super::*;
+    use crate::trajectory::slalom::SlalomGenerator;
+    use uom::si::f32::{Acceleration, Jerk};
+
+    #[test]
+    fn test_displacement_of_arc() {
+        let turn = ClothoidTurn::new(Angle::new::<degree>(90.0), Length::new::<meter>(0.1), 0.0);
+        let (x, y) = turn.displacement();
+        assert_relative_eq!(x.get::<meter>(), 0.1, epsilon = 1e-5);
+        assert_relative_eq!(y.get::<meter>(), 0.1, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_evaluate_clothoid_trajectory() {
+        let period = Time::new::<second>(0.001);
+        let turn = ClothoidTurn::new(
+            Angle::new::<degree>(-135.0),
+            Length::new::<meter>(0.05),
+            0.5,
+        );
+        let trajectory = ClothoidTrajectory::new(
+            turn,
+            Length::new::<meter>(0.01),
+            Velocity::new::<meter_per_second>(0.5),
+            period,
+        );
+        for (i, target) in trajectory.clone().enumerate() {
+            let expected = trajectory.evaluate(period * i as f32);
+            assert_relative_eq!(target.x.x.value, expected.x.x.value, epsilon = 1e-4);
+            assert_relative_eq!(target.y.x.value, expected.y.x.value, epsilon = 1e-4);
+            assert_relative_eq!(target.theta.x.value, expected.theta.x.value, epsilon = 1e-5);
+        }
+        let (dx, dy) = turn.displacement();
+        let end = trajectory.end_pose();
+        assert_relative_eq!(
+            end.x.get::<meter>(),
+            0.01 + dx.get::<meter>(),
+            epsilon = 1e-4
+        );
+        assert_relative_eq!(end.y.get::<meter>(), dy.get::<meter>(), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn test_clothoid_slalom() {
+        let square_width = 0.09;
+        let generator = SlalomGenerator::new(
+            Time::new::<second>(0.001),
+            Velocity::new::<meter_per_second>(1.0),
+            Acceleration::new::<meter_per_second_squared>(1.0),
+            Jerk::new::<meter_per_second_cubed>(1.0),
+        );
+        let v = Velocity::new::<meter_per_second>(0.5);
+        let ends = [
+            (1.0, 0.5, 45.0),
+            (1.0, 1.0, 90.0),
+            (0.5, 1.0, 135.0),
+            (0.0, 1.0, 180.0),
+            (SQRT_2 / 2.0, SQRT_2 / 2.0, 90.0),
+            (1.0, -1.0, -90.0),
+        ];
+        for (x, y, theta) in ends {
+            let end = Pose {
+                x: Length::new::<meter>(x * square_width),
+                y: Length::new::<meter>(y * square_width),
+                theta: Angle::new::<degree>(theta),
+            };
+            let min_straight = Length::new::<meter>(0.01);
+            let slalom = ClothoidSlalom::solve(&end, 0.5, min_straight).unwrap();
+            assert!(slalom.l_start >= min_straight && slalom.l_end >= min_straight);
+
+            let check = |target: Target| {
+                assert_relative_eq!(
+                    target.x.x.get::<meter>(),
+                    end.x.get::<meter>(),
+                    epsilon = 1e-3
+                );
+                assert_relative_eq!(
+                    target.y.x.get::<meter>(),
+                    end.y.get::<meter>(),
+                    epsilon = 1e-3
+                );
+                assert_relative_eq!(
+                    target.theta.x.get::<degree>(),
+                    end.theta.get::<degree>(),
+                    epsilon = 0.5
+                );
+            };
+            check(
+                generator
+                    .generate_clothoid_slalom(&slalom, v)
+                    .last()
+                    .unwrap(),
+            );
+
+            // the existing generator follows the same geometry.
+            let params = slalom.parameters(
+                Velocity::new::<meter_per_second>(0.3),
+                AngularJerk::new::<radian_per_second_cubed>(1e5),
+            );
+            check(
+                generator
+                    .generate_constant_slalom(params, v)
+                    .last()
+                    .unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_clothoid_slalom_does_not_fit() {
+        let end = Pose {
+            x: Length::new::<meter>(0.09),
+            y: Length::new::<meter>(0.09),
+            theta: Angle::new::<degree>(90.0),
+        };
+        assert_eq!(
+            ClothoidSlalom::solve(&end, 0.5, Length::new::<meter>(0.1)),
+            Err(SlalomError::DoesNotFit)
+        );
+        let end = Pose {
+            theta: Default::default(),
+            ..end
+        };
+        assert_eq!(
+            ClothoidSlalom::solve(&end, 0.5, Default::default()),
+            Err(SlalomError::InvalidAngle)
+        );
+    }
+}
@@ -0,0 +1,465 @@
+use heapless::Deque;
+#[allow(unused_imports)]
+use micromath::F32Ext;
+use typed_builder::TypedBuilder;
+use uom::si::{
+    electric_potential::volt,
+    f32::{ElectricPotential, Length, Time, Velocity},
+    velocity::meter_per_second,
+};
+
+use crate::{
+    control::Target,
+    trajectory::{scale_target, TimedTrajectory},
+};
+
+/// The result of [`FeasibilityAnalyzer::analyze`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FeasibilityReport {
+    /// The maximum absolute velocity of the wheels.
+    pub peak_wheel_velocity: Velocity,
+    /// The maximum absolute feedforward voltage of the motors.
+    pub peak_voltage: ElectricPotential,
+    /// The time of the first target which violates either limit.
+    pub first_violation: Option<Time>,
+}
+
+impl FeasibilityReport {
+    pub fn is_feasible(&self) -> bool {
+        self.first_violation.is_none()
+    }
+}
+
+/// An analyzer which checks wheel velocities and motor voltages required by trajectories.
+///
+/// Voltages are the feedforward terms of [`Controller`](crate::control::Controller)
+/// for first-order motor models of translation and rotation.
+#[derive(Debug, TypedBuilder)]
+pub struct FeasibilityAnalyzer {
+    trans_k: f32,
+    trans_t1: Time,
+    rot_k: f32,
+    rot_t1: Time,
+    wheel_interval: Length,
+    max_wheel_velocity: Velocity,
+    max_voltage: ElectricPotential,
+    period: Time,
+}
+
+// The number of iterations of bisection in `FeasibilityAnalyzer::required_scale`.
+const SCALE_BISECTION_COUNT: usize = 16;
+
+/// The minimum scale of [`LocallyScaledTrajectory`].
+///
+/// Targets which need a smaller scale are emitted at this scale, so the scaled trajectory
+/// takes at most `1 / MIN_SCALE` times as long as the original one.
+pub const MIN_SCALE: f32 = 0.05;
+
+/// Error on scaling trajectories.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScaleError {
+    /// The scale of [`TimeScaledTrajectory`](crate::trajectory::TimeScaledTrajectory)
+    /// is not positive and finite.
+    InvalidScale,
+    /// The iterator emits more targets than
+    /// [`SampledTrajectory`](crate::trajectory::SampledTrajectory) holds.
+    TooManyTargets,
+    /// The iterator emits no targets.
+    NoTargets,
+    /// The maximum rate of the scale is not positive and finite.
+    InvalidScaleRate,
+    /// The lookahead is not positive and finite.
+    InvalidLookahead,
+}
+
+// The number of samples in the lookahead of `LocallyScaledTrajectory`.
+const LOOKAHEAD_SAMPLE_COUNT: usize = 64;
+
+impl FeasibilityAnalyzer {
+    /// Analyzes targets emitted every period from time zero.
+    pub fn analyze(&self, trajectory: impl IntoIterator<Item = Target>) -> FeasibilityReport {
+        let mut report = FeasibilityReport::default();
+        for (i, target) in trajectory.into_iter().enumerate() {
+            let (wheel_velocity, voltage) = self.demand(&target, 1.0, 0.0);
+            let wheel_velocity = Velocity::new::<meter_per_second>(wheel_velocity);
+            let voltage = ElectricPotential::new::<volt>(voltage);
+            if wheel_velocity > report.peak_wheel_velocity {
+                report.peak_wheel_velocity = wheel_velocity;
+            }
+            if voltage > report.peak_voltage {
+                report.peak_voltage = voltage;
+            }
+            if report.first_violation.is_none() && !self.is_feasible(&target, 1.0, 0.0) {
+                report.first_violation = Some(self.period * i as f32);
+            }
+        }
+        report
+    }
+
+    /// Returns the largest scale not exceeding one with which
+    /// [`TimeScaledTrajectory`](crate::trajectory::TimeScaledTrajectory) makes
+    /// `trajectory` feasible at the given targets.
+    pub fn feasible_scale(&self, trajectory: impl IntoIterator<Item = Target>) -> f32 {
+        trajectory
+            .into_iter()
+            .map(|target| self.required_scale(&target))
+            .fold(1.0, f32::min)
+    }
+
+    /// Returns `trajectory` slowed down only where it is infeasible.
+    ///
+    /// The scale changes by at most `max_scale_rate` per second and starts to decrease
+    /// `lookahead` before infeasible targets, which should be long enough to reach the
+    /// required scale. The targets within `lookahead` are sampled at 64 points, so a dip
+    /// of the required scale shorter than the interval of them may be missed.
+    /// The scale increases only while the acceleration caused by the increase is feasible,
+    /// while it decreases regardless of that, so `max_scale_rate` should be small enough.
+    pub fn scale_locally<T>(
+        &self,
+        trajectory: T,
+        max_scale_rate: f32,
+        lookahead: Time,
+    ) -> Result<LocallyScaledTrajectory<'_, T>, ScaleError>
+    where
+        T: TimedTrajectory,
+    {
+        if !(max_scale_rate.is_finite() && max_scale_rate > 0.0) {
+            return Err(ScaleError::InvalidScaleRate);
+        }
+        if !(lookahead.value.is_finite() && lookahead.value > 0.0) {
+            return Err(ScaleError::InvalidLookahead);
+        }
+        let mut trajectory = LocallyScaledTrajectory {
+            inner: trajectory,
+            analyzer: self,
+            max_scale_rate,
+            step: lookahead / LOOKAHEAD_SAMPLE_COUNT as f32,
+            first: 0,
+            required: Deque::new(),
+            t: Default::default(),
+            scale: 1.0,
+            done: false,
+        };
+        trajectory.scale = trajectory.lookahead_scale();
+        Ok(trajectory)
+    }
+
+    // Return the wheel velocity and the voltage required by `target` running `scale` times
+    // as fast, where the scale changes by `dscale` per second.
+    fn demand(&self, target: &Target, scale: f32, dscale: f32) -> (f32, f32) {
+        let (sin, cos) = (target.theta.x.value.sin(), target.theta.x.value.cos());
+        let v = target.x.v.value * cos + target.y.v.value * sin;
+        let a = target.x.a.value * cos + target.y.a.value * sin;
+        let omega = target.theta.v.value;
+        let alpha = target.theta.a.value;
+        let (v, a) = (v * scale, a * scale * scale + v * dscale);
+        let (omega, alpha) = (omega * scale, alpha * scale * scale + omega * dscale);
+
+        let dv = omega * self.wheel_interval.value / 2.0;
+        let vol_t = (a * self.trans_t1.value + v) / self.trans_k;
+        let vol_r = (alpha * self.rot_t1.value + omega) / self.rot_k;
+        (v.abs() + dv.abs(), vol_t.abs() + vol_r.abs())
+    }
+
+    // Return true if `target` running `scale` times as fast is feasible,
+    // where the scale changes by `dscale` per second.
+    fn is_feasible(&self, target: &Target, scale: f32, dscale: f32) -> bool {
+        let (wheel_velocity, voltage) = self.demand(target, scale, dscale);
+        wheel_velocity <= self.max_wheel_velocity.value && voltage <= self.max_voltage.value
+    }
+
+    // Return the largest constant scale not exceeding one which makes `target` feasible.
+    fn required_scale(&self, target: &Target) -> f32 {
+        if self.is_feasible(target, 1.0, 0.0) {
+            return 1.0;
+        }
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..SCALE_BISECTION_COUNT {
+            let middle = (low + high) / 2.0;
+            if self.is_feasible(target, middle, 0.0) {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        low
+    }
+}
+
+/// A trajectory slowed down by [`FeasibilityAnalyzer::scale_locally`].
+pub struct LocallyScaledTrajectory<'a, T> {
+    inner: T,
+    analyzer: &'a FeasibilityAnalyzer,
+    max_scale_rate: f32,
+    // the interval of the samples within the lookahead
+    step: Time,
+    // the index of the first sample in `required`
+    first: usize,
+    // the required scales of the samples from `first`, which are kept while the window
+    // advances so that each sample is solved only once.
+    required: Deque<f32, LOOKAHEAD_SAMPLE_COUNT>,
+    // the time of the original trajectory
+    t: Time,
+    scale: f32,
+    done: bool,
+}
+
+impl<'a, T> LocallyScaledTrajectory<'a, T>
+where
+    T: TimedTrajectory,
+{
+    // Return the largest scale at `t` from which the scale can decrease in time
+    // for the targets within the lookahead.
+    fn lookahead_scale(&mut self) -> f32 {
+        // the time of the scaled trajectory is not shorter than that of the original one.
+        // samples are on a fixed grid so that the bound changes smoothly with `t`,
+        // and each of them also covers the step before it, but not the time before `t`.
+        let first = (self.t / self.step).value.ceil() as usize;
+        while self.first < first {
+            if self.required.pop_front().is_none() {
+                self.first = first;
+                break;
+            }
+            self.first += 1;
+        }
+        while !self.required.is_full() {
+            let sample = self.step * (self.first + self.required.len()) as f32;
+            let scale = self.analyzer.required_scale(&self.inner.evaluate(sample));
+            self.required.push_back(scale).ok();
+        }
+
+        let (t, step, rate) = (self.t, self.step, self.max_scale_rate);
+        let current = self.analyzer.required_scale(&self.inner.evaluate(t));
+        self.required
+            .iter()
+            .enumerate()
+            .map(|(i, required)| {
+                let sample = step * (self.first + i) as f32;
+                required + rate * (sample - step - t).value.max(0.0)
+            })
+            .fold(current, f32::min)
+            .max(MIN_SCALE)
+    }
+}
+
+impl<'a, T> Iterator for LocallyScaledTrajectory<'a, T>
+where
+    T: TimedTrajectory,
+{
+    type Item = Target;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let duration = self.inner.duration();
+        if self.t >= duration {
+            self.done = true;
+            self.t = duration;
+        }
+        let period = self.analyzer.period;
+        let target = self.inner.evaluate(self.t);
+        let mut next_scale = self
+            .lookahead_scale()
+            .min(self.scale + self.max_scale_rate * period.value)
+            .max(self.scale - self.max_scale_rate * period.value)
+            .max(MIN_SCALE);
+        // the scale only increases while the acceleration caused by it is feasible.
+        // the acceleration is not charged when the scale decreases or stays,
+        // since the scale must reach the lookahead bound in time.
+        if next_scale > self.scale {
+            let dscale = (next_scale - self.scale) / period.value;
+            if !self.analyzer.is_feasible(&target, self.scale, dscale) {
+                next_scale = self.scale;
+            }
+        }
+        let dscale = (next_scale - self.scale) / period.value;
+        let target = scale_target(target, self.scale, dscale);
+        self.t += period * (self.scale + next_scale) / 2.0;
+        self.scale = next_scale;
+        Some(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use uom::si::{
+        acceleration::meter_per_second_squared, f32::Acceleration, f32::Jerk,
+        jerk::meter_per_second_cubed, length::millimeter, time::second,
+    };
+
+    use super::*;
+    use crate::trajectory::{
+        slalom::{SlalomConfig, SlalomDirection, SlalomGenerator, SlalomKind},
+        straight::StraightGenerator,
+        ChainTrajectory, SampledTrajectory, ShiftTrajectory, TimeScaledTrajectory,
+    };
+
+    fn analyzer(period: Time) -> FeasibilityAnalyzer {
+        FeasibilityAnalyzer::builder()
+            .trans_k(1.865)
+            .trans_t1(Time::new::<second>(0.4443))
+            .rot_k(82.39)
+            .rot_t1(Time::new::<second>(0.2855))
+            .wheel_interval(Length::new::<millimeter>(33.5))
+            .max_wheel_velocity(Velocity::new::<meter_per_second>(2.0))
+            .max_voltage(ElectricPotential::new::<volt>(3.7))
+            .period(period)
+            .build()
+    }
+
+    #[test]
+    fn test_feasible_straight() {
+        let period = Time::new::<second>(0.001);
+        let v_max = Velocity::new::<meter_per_second>(1.0);
+        let generator = StraightGenerator::new(
+            v_max,
+            Acceleration::new::<meter_per_second_squared>(5.0),
+            Jerk::new::<meter_per_second_cubed>(100.0),
+            period,
+        );
+        let report = analyzer(period).analyze(generator.generate(
+            Length::new::<millimeter>(360.0),
+            Default::default(),
+            Default::default(),
+        ));
+        assert!(report.is_feasible());
+        assert_relative_eq!(report.peak_wheel_velocity.value, 1.0, epsilon = 1e-3);
+        assert!(report.peak_voltage.get::<volt>() > 1.0 / 1.865);
+    }
+
+    #[test]
+    fn test_infeasible_slalom() {
+        let period = Time::new::<second>(0.001);
+        let v_max = Velocity::new::<meter_per_second>(3.0);
+        let generator = SlalomGenerator::new(
+            period,
+            v_max,
+            Acceleration::new::<meter_per_second_squared>(5.0),
+            Jerk::new::<meter_per_second_cubed>(100.0),
+        );
+        let params =
+            SlalomConfig::default().parameters(SlalomKind::Search90, SlalomDirection::Left);
+        let analyzer = analyzer(period);
+
+        let v_ref = params.v_ref;
+        let report = analyzer.analyze(generator.generate_constant_slalom(params.clone(), v_ref));
+        assert!(report.is_feasible());
+
+        // the outer wheel is faster than the center of the robot.
+        let v = Velocity::new::<meter_per_second>(1.9);
+        let report = analyzer.analyze(generator.generate_constant_slalom(params, v));
+        assert!(report.peak_wheel_velocity > v);
+        let t = report.first_violation.unwrap();
+        assert!(t > Time::default());
+    }
+
+    #[test]
+    fn test_scale_slalom() {
+        let period = Time::new::<second>(0.001);
+        let generator = SlalomGenerator::new(
+            period,
+            Velocity::new::<meter_per_second>(3.0),
+            Acceleration::new::<meter_per_second_squared>(5.0),
+            Jerk::new::<meter_per_second_cubed>(100.0),
+        );
+        let params =
+            SlalomConfig::default().parameters(SlalomKind::Search90, SlalomDirection::Left);
+        let analyzer = analyzer(period);
+        let trajectory =
+            generator.generate_constant_slalom(params, Velocity::new::<meter_per_second>(1.9));
+        assert!(!analyzer.analyze(trajectory.clone()).is_feasible());
+        let end = trajectory.end_pose();
+
+        let scale = analyzer.feasible_scale(trajectory.clone());
+        assert!(0.0 < scale && scale < 1.0);
+        let uniform = TimeScaledTrajectory::new(trajectory.clone(), scale, period).unwrap();
+        assert_relative_eq!(
+            uniform.duration().value,
+            trajectory.duration().value / scale,
+            epsilon = 1e-4
+        );
+        let uniform_end = uniform.end_pose();
+        assert_relative_eq!(uniform_end.x.value, end.x.value, epsilon = 1e-4);
+        assert_relative_eq!(uniform_end.y.value, end.y.value, epsilon = 1e-4);
+        // the scaled trajectory is sampled between the original targets.
+        let report = analyzer.analyze(uniform);
+        assert!(report.peak_wheel_velocity.get::<meter_per_second>() < 2.0 * 1.01);
+        assert!(report.peak_voltage.get::<volt>() < 3.7 * 1.01);
+
+        // the straight parts keep running fast.
+        let v = Velocity::new::<meter_per_second>(1.9);
+        let straight = StraightGenerator::new(
+            v,
+            Acceleration::new::<meter_per_second_squared>(5.0),
+            Jerk::new::<meter_per_second_cubed>(100.0),
+            period,
+        )
+        .generate(Length::new::<millimeter>(720.0), Default::default(), v);
+        let pose = straight.end_pose();
+        let trajectory = ChainTrajectory::new(straight, ShiftTrajectory::new(pose, trajectory));
+        let end = trajectory.end_pose();
+        let scale = analyzer.feasible_scale(trajectory.clone());
+        let targets = analyzer
+            .scale_locally(trajectory.clone(), 2.0, Time::new::<second>(0.4))
+            .unwrap()
+            .collect::<std::vec::Vec<_>>();
+        let duration = period * (targets.len() - 1) as f32;
+        assert!(duration > trajectory.duration());
+        assert!(duration < trajectory.duration() / scale);
+        let last = targets.last().unwrap();
+        assert_relative_eq!(last.x.x.value, end.x.value, epsilon = 1e-4);
+        assert_relative_eq!(last.y.x.value, end.y.value, epsilon = 1e-4);
+        assert!(analyzer.analyze(targets).is_feasible());
+
+        // a plain iterator is scaled through the samples of it.
+        let sampled = SampledTrajectory::<1024>::new(trajectory.take(usize::MAX), period).unwrap();
+        let sampled_duration = sampled.duration();
+        let targets = analyzer
+            .scale_locally(sampled, 2.0, Time::new::<second>(0.4))
+            .unwrap()
+            .collect::<std::vec::Vec<_>>();
+        let duration = period * (targets.len() - 1) as f32;
+        assert!(duration > sampled_duration);
+        assert!(duration < sampled_duration / scale);
+        assert!(analyzer.analyze(targets).is_feasible());
+    }
+
+    #[test]
+    fn test_scale_feasible_straight_locally() {
+        let period = Time::new::<second>(0.001);
+        let v = Velocity::new::<meter_per_second>(1.0);
+        let straight = StraightGenerator::new(
+            v,
+            Acceleration::new::<meter_per_second_squared>(5.0),
+            Jerk::new::<meter_per_second_cubed>(100.0),
+            period,
+        )
+        .generate(Length::new::<millimeter>(180.0), v, v);
+        let analyzer = analyzer(period);
+        let lookahead = Time::new::<second>(0.1);
+
+        // a fast change of the scale does not stop a feasible trajectory.
+        let targets = analyzer
+            .scale_locally(straight.clone(), 20.0, lookahead)
+            .unwrap()
+            .collect::<std::vec::Vec<_>>();
+        // the end of the trajectory is emitted exactly.
+        assert!(targets.len() <= straight.clone().count() + 1);
+        for target in targets {
+            assert_relative_eq!(target.x.v.value, v.value, epsilon = 1e-4);
+        }
+
+        assert_eq!(
+            analyzer
+                .scale_locally(straight.clone(), 0.0, lookahead)
+                .err(),
+            Some(ScaleError::InvalidScaleRate)
+        );
+        assert_eq!(
+            analyzer.scale_locally(straight, 2.0, Time::default()).err(),
+            Some(ScaleError::InvalidLookahead)
+        );
+    }
+}
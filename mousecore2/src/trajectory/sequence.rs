@@ -0,0 +1,351 @@
+use core::f32::consts::PI;
+
+use heapless::Deque;
+#[allow(unused_imports)]
+use micromath::F32Ext;
+use uom::si::f32::{Time, Velocity};
+
+use crate::{
+    control::Target,
+    trajectory::{
+        slalom::{
+            AcceleratingSlalomTrajectory, ClothoidSlalomTrajectory, SSlalomTrajectory,
+            SlalomTrajectory,
+        },
+        spin::{PivotTrajectory, SpinTrajectory},
+        straight::StraightTrajectory,
+        ShiftTrajectory, StopTrajectory, TimedTrajectory,
+    },
+    wall::Pose,
+};
+
+/// A segment of [`TrajectorySequence`].
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone)]
+pub enum Segment {
+    Straight(StraightTrajectory),
+    Slalom(SlalomTrajectory),
+    SSlalom(SSlalomTrajectory),
+    AcceleratingSlalom(AcceleratingSlalomTrajectory),
+    ClothoidSlalom(ClothoidSlalomTrajectory),
+    Spin(SpinTrajectory),
+    Pivot(PivotTrajectory),
+    Stop(StopTrajectory),
+}
+
+macro_rules! impl_from_trajectory {
+    ($($variant: ident($trajectory: ty)),*) => {
+        $(
+            impl From<$trajectory> for Segment {
+                fn from(value: $trajectory) -> Self {
+                    Self::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_trajectory!(
+    Straight(StraightTrajectory),
+    Slalom(SlalomTrajectory),
+    SSlalom(SSlalomTrajectory),
+    AcceleratingSlalom(AcceleratingSlalomTrajectory),
+    ClothoidSlalom(ClothoidSlalomTrajectory),
+    Spin(SpinTrajectory),
+    Pivot(PivotTrajectory),
+    Stop(StopTrajectory)
+);
+
+macro_rules! dispatch {
+    ($self: expr, $inner: ident => $expr: expr) => {
+        match $self {
+            Segment::Straight($inner) => $expr,
+            Segment::Slalom($inner) => $expr,
+            Segment::SSlalom($inner) => $expr,
+            Segment::AcceleratingSlalom($inner) => $expr,
+            Segment::ClothoidSlalom($inner) => $expr,
+            Segment::Spin($inner) => $expr,
+            Segment::Pivot($inner) => $expr,
+            Segment::Stop($inner) => $expr,
+        }
+    };
+}
+
+impl Iterator for Segment {
+    type Item = Target;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        dispatch!(self, inner => inner.next())
+    }
+}
+
+impl TimedTrajectory for Segment {
+    fn duration(&self) -> Time {
+        dispatch!(self, inner => inner.duration())
+    }
+
+    fn evaluate(&self, t: Time) -> Target {
+        dispatch!(self, inner => inner.evaluate(t))
+    }
+
+    fn seek(&mut self, t: Time) {
+        dispatch!(self, inner => inner.seek(t))
+    }
+
+    fn next_time(&self) -> Time {
+        dispatch!(self, inner => inner.next_time())
+    }
+}
+
+/// Error on pushing a segment to [`TrajectorySequence`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SequenceError {
+    /// The sequence already holds its capacity of segments.
+    Full,
+    /// The duration of the segment is negative or NaN.
+    InvalidDuration,
+}
+
+/// The end of a segment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SegmentEnd {
+    /// The target at the end of the segment.
+    pub target: Target,
+}
+
+impl SegmentEnd {
+    pub fn pose(&self) -> Pose {
+        Pose {
+            x: self.target.x.x,
+            y: self.target.y.x,
+            theta: self.target.theta.x,
+        }
+    }
+
+    /// Returns the signed translational velocity along the heading.
+    pub fn velocity(&self) -> Velocity {
+        let (sin, cos) = (
+            self.target.theta.x.value.sin(),
+            self.target.theta.x.value.cos(),
+        );
+        self.target.x.v * cos + self.target.y.v * sin
+    }
+}
+
+struct Entry {
+    trajectory: ShiftTrajectory<Segment>,
+    end: SegmentEnd,
+}
+
+/// A fixed-capacity sequence of trajectories.
+///
+/// Each segment is placed at a pose and the segments are emitted one after another.
+/// In debug builds, pushing a segment whose start does not continue from the end of
+/// the previous one panics.
+pub struct TrajectorySequence<const N: usize> {
+    entries: Deque<Entry, N>,
+    last: Option<SegmentEnd>,
+    period: Time,
+}
+
+impl<const N: usize> TrajectorySequence<N> {
+    // Margins of continuity in addition to the change in a period.
+    const POSITION_MARGIN: f32 = 1e-3;
+    const ANGLE_MARGIN: f32 = 1e-2;
+    const VELOCITY_MARGIN: f32 = 1e-2;
+
+    pub fn new(period: Time) -> Self {
+        Self {
+            entries: Deque::new(),
+            last: None,
+            period,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.entries.is_full()
+    }
+
+    /// Returns the end of the last pushed segment.
+    pub fn end(&self) -> Option<&SegmentEnd> {
+        self.last.as_ref()
+    }
+
+    /// Returns the ends of the remaining segments.
+    pub fn ends(&self) -> impl Iterator<Item = &SegmentEnd> {
+        self.entries.iter().map(|entry| &entry.end)
+    }
+
+    /// Pushes `segment` placed at `pose`.
+    ///
+    /// The ends of the segment are evaluated from its duration without iterating it.
+    pub fn push(&mut self, pose: Pose, segment: impl Into<Segment>) -> Result<(), SequenceError> {
+        if self.entries.is_full() {
+            return Err(SequenceError::Full);
+        }
+        let trajectory = ShiftTrajectory::new(pose, segment.into());
+        let duration = trajectory.duration();
+        if duration.value.is_nan() || duration.value < 0.0 {
+            return Err(SequenceError::InvalidDuration);
+        }
+        let start = trajectory.evaluate(Default::default());
+        let end = SegmentEnd {
+            target: trajectory.evaluate(duration),
+        };
+        if let Some(last) = &self.last {
+            debug_assert!(
+                self.is_continuous(&last.target, &start),
+                "discontinuous trajectories: {:?} -> {:?}",
+                last.target,
+                start
+            );
+        }
+        self.last = Some(end);
+        self.entries
+            .push_back(Entry { trajectory, end })
+            .map_err(|_| SequenceError::Full)
+    }
+
+    // Return true if `next` can follow `prev` in a period.
+    fn is_continuous(&self, prev: &Target, next: &Target) -> bool {
+        let dt = self.period.value;
+        let position = |prev: f32, next: f32, v: f32| {
+            (next - prev).abs() <= v.abs() * dt + Self::POSITION_MARGIN
+        };
+        let velocity = |prev: f32, next: f32, a: f32| {
+            (next - prev).abs() <= a.abs() * dt + Self::VELOCITY_MARGIN
+        };
+        // headings which differ by a turn are the same.
+        let angle_diff = (next.theta.x - prev.theta.x).value.rem_euclid(2.0 * PI);
+        let angle_diff = angle_diff.min(2.0 * PI - angle_diff);
+        position(prev.x.x.value, next.x.x.value, prev.x.v.value)
+            && position(prev.y.x.value, next.y.x.value, prev.y.v.value)
+            && angle_diff <= prev.theta.v.value.abs() * dt + Self::ANGLE_MARGIN
+            && velocity(prev.x.v.value, next.x.v.value, prev.x.a.value)
+            && velocity(prev.y.v.value, next.y.v.value, prev.y.a.value)
+    }
+}
+
+impl<const N: usize> Iterator for TrajectorySequence<N> {
+    type Item = Target;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = self.entries.front_mut()?;
+            if let Some(target) = entry.trajectory.next() {
+                return Some(target);
+            }
+            self.entries.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use uom::si::{
+        acceleration::meter_per_second_squared,
+        angle::degree,
+        f32::{Acceleration, Angle, Jerk, Length},
+        jerk::meter_per_second_cubed,
+        length::meter,
+        time::second,
+        velocity::meter_per_second,
+    };
+
+    use super::*;
+    use crate::trajectory::{
+        slalom::{SlalomConfig, SlalomDirection, SlalomGenerator, SlalomKind},
+        straight::StraightGenerator,
+    };
+
+    fn generators(period: Time) -> (StraightGenerator, SlalomGenerator) {
+        let v_max = Velocity::new::<meter_per_second>(1.0);
+        let a_max = Acceleration::new::<meter_per_second_squared>(5.0);
+        let j_max = Jerk::new::<meter_per_second_cubed>(100.0);
+        (
+            StraightGenerator::new(v_max, a_max, j_max, period),
+            SlalomGenerator::new(period, v_max, a_max, j_max),
+        )
+    }
+
+    #[test]
+    fn test_sequence() {
+        let period = Time::new::<second>(0.001);
+        let (straight, slalom) = generators(period);
+        let v = Velocity::new::<meter_per_second>(0.5);
+        let params =
+            SlalomConfig::default().parameters(SlalomKind::Search90, SlalomDirection::Left);
+        let square_width = Length::new::<meter>(0.09);
+
+        let mut sequence = TrajectorySequence::<3>::new(period);
+        assert!(sequence
+            .push(
+                Pose::default(),
+                straight.generate(square_width, Default::default(), v),
+            )
+            .is_ok());
+        let pose = sequence.end().unwrap().pose();
+        assert_relative_eq!(
+            sequence.end().unwrap().velocity().value,
+            v.value,
+            epsilon = 1e-3
+        );
+        assert!(sequence
+            .push(pose, slalom.generate_constant_slalom(params, v))
+            .is_ok());
+        let pose = sequence.end().unwrap().pose();
+        assert_relative_eq!(pose.theta.get::<degree>(), 90.0, epsilon = 1e-1);
+        assert!(sequence
+            .push(pose, straight.generate(square_width, v, Default::default()))
+            .is_ok());
+        assert!(sequence.is_full());
+        assert_eq!(
+            sequence.push(pose, straight.generate(square_width, v, v)),
+            Err(SequenceError::Full)
+        );
+
+        let end = *sequence.end().unwrap();
+        let ends = sequence.ends().copied().collect::<std::vec::Vec<_>>();
+        assert_eq!(ends.len(), 3);
+        let targets = sequence.collect::<std::vec::Vec<_>>();
+        let last = targets.last().unwrap();
+        assert_relative_eq!(last.x.x.value, end.target.x.x.value, epsilon = 1e-3);
+        assert_relative_eq!(last.y.x.value, end.target.y.x.value, epsilon = 1e-3);
+        assert_relative_eq!(end.velocity().value, 0.0, epsilon = 1e-3);
+        assert_relative_eq!(end.pose().x.value, ends[1].pose().x.value, epsilon = 1e-3);
+    }
+
+    #[test]
+    #[should_panic(expected = "discontinuous")]
+    fn test_discontinuous_sequence() {
+        let period = Time::new::<second>(0.001);
+        let (straight, _) = generators(period);
+        let v = Velocity::new::<meter_per_second>(0.5);
+        let distance = Length::new::<meter>(0.09);
+
+        let mut sequence = TrajectorySequence::<2>::new(period);
+        assert!(sequence
+            .push(
+                Pose::default(),
+                straight.generate(distance, Default::default(), v),
+            )
+            .is_ok());
+        // the second straight does not start at the end of the first one.
+        let _ = sequence.push(
+            Pose {
+                theta: Angle::new::<degree>(90.0),
+                ..Default::default()
+            },
+            straight.generate(distance, v, v),
+        );
+    }
+}
@@ -1,5 +1,4 @@
-use core::f32::consts::PI;
-use core::iter::Chain;
+use core::f32::consts::{PI, SQRT_2};
 use core::marker::PhantomData;
 
 #[allow(unused_imports)]
@@ -8,21 +7,26 @@ use micromath::F32Ext;
 use proptest_derive::Arbitrary;
 use serde::{Deserialize, Serialize};
 use uom::si::{
+    acceleration::meter_per_second_squared,
     f32::{
         Acceleration, Angle, AngularAcceleration, AngularJerk, AngularVelocity, Jerk, Length, Time,
         Velocity,
     },
+    jerk::meter_per_second_cubed,
     ratio::ratio,
+    velocity::meter_per_second,
 };
 
 use crate::{
-    control::{LengthTarget, Target},
+    control::{AngleTarget, LengthTarget, Target},
     trajectory::{
+        clothoid::{ClothoidSlalom, ClothoidTrajectory},
+        integrate_heading,
         straight::{
-            AngleOverallCalculator, AngleStraightCalculatorGenerator, StraightGenerator,
-            StraightTrajectory,
+            AngleOverallCalculator, AngleStraightCalculatorGenerator, LengthOverallCalculator,
+            LengthStraightCalculatorGenerator, StraightGenerator, StraightTrajectory,
         },
-        ShiftTrajectory,
+        ChainTrajectory, ShiftTrajectory, TimedTrajectory, TrajectorySummary,
     },
     wall::Pose,
 };
@@ -48,6 +52,65 @@ pub enum SlalomKind {
     FastRun135Rev,
     FastRun180,
     FastRunDiagonal90,
+    FastRunDiagonal90Large,
+    /// An S-turn which shifts the robot by one square sideways.
+    ///
+    /// Trajectories of this kind should be generated by
+    /// [`SlalomGenerator::generate_s_slalom_with_terminal_velocity`].
+    FastRunKojima,
+}
+
+impl SlalomKind {
+    /// All kinds of slaloms.
+    pub const ALL: [SlalomKind; 10] = [
+        SlalomKind::Search90,
+        SlalomKind::FastRun45,
+        SlalomKind::FastRun45Rev,
+        SlalomKind::FastRun90,
+        SlalomKind::FastRun135,
+        SlalomKind::FastRun135Rev,
+        SlalomKind::FastRun180,
+        SlalomKind::FastRunDiagonal90,
+        SlalomKind::FastRunDiagonal90Large,
+        SlalomKind::FastRunKojima,
+    ];
+
+    /// Returns the end pose of the left slalom of this kind from the origin facing along the x-axis.
+    ///
+    /// For [`SlalomKind::FastRunKojima`], it is the end pose of the first half of the S-turn.
+    pub fn end_pose(&self, square_width: Length, search_front_offset: Length) -> Pose {
+        use uom::si::angle::degree;
+        use SlalomKind::*;
+
+        let offset = (search_front_offset / square_width).get::<ratio>();
+        let quarter = SQRT_2 / 4.0;
+        let (x, y, theta) = match self {
+            Search90 => (0.5 - offset, 0.5 + offset, 90.0),
+            FastRun45 => (1.0, 0.5, 45.0),
+            FastRun45Rev => (3.0 * quarter, quarter, 45.0),
+            FastRun90 => (1.0, 1.0, 90.0),
+            FastRun135 => (0.5, 1.0, 135.0),
+            FastRun135Rev => (quarter, 3.0 * quarter, 135.0),
+            FastRun180 => (0.0, 1.0, 180.0),
+            FastRunDiagonal90 => (2.0 * quarter, 2.0 * quarter, 90.0),
+            FastRunDiagonal90Large => (4.0 * quarter, 4.0 * quarter, 90.0),
+            FastRunKojima => (1.0, 0.5, 60.0),
+        };
+        Pose {
+            x: square_width * x,
+            y: square_width * y,
+            theta: Angle::new::<degree>(theta),
+        }
+    }
+}
+
+/// Error on designing slaloms.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SlalomError {
+    /// The turn angle is zero or out of range.
+    InvalidAngle,
+    /// The curve does not fit between the start and the end poses.
+    DoesNotFit,
 }
 
 /// Parameters for slalom.
@@ -64,8 +127,214 @@ pub struct SlalomParameters {
     pub dddtheta: AngularJerk,
 }
 
+impl SlalomParameters {
+    fn end_pose(&self) -> Pose {
+        Pose {
+            x: self.x_curve_end + self.l_end * self.theta.value.cos(),
+            y: self.y_curve_end + self.l_end * self.theta.value.sin(),
+            theta: self.theta,
+        }
+    }
+
+    /// Returns the duration of the curve at the velocity `v`.
+    pub fn curve_duration(&self, v: Velocity) -> Time {
+        let k = (v / self.v_ref).get::<ratio>();
+        let (_, t_end) = AngleStraightCalculatorGenerator::new(
+            k * self.dtheta,
+            k * k * self.ddtheta,
+            k * k * k * self.dddtheta,
+        )
+        .generate(
+            Default::default(),
+            self.theta,
+            Default::default(),
+            Default::default(),
+        );
+        t_end
+    }
+
+    // Return the parameters of `self` rotated by 180 degrees and traced backward.
+    fn reflected(&self) -> Self {
+        Self {
+            l_start: self.l_end,
+            l_end: self.l_start,
+            x_curve_end: self.l_end + self.x_curve_end - self.l_start,
+            y_curve_end: -self.y_curve_end,
+            theta: -self.theta,
+            ..self.clone()
+        }
+    }
+}
+
+/// Limits of the angular profile of a slalom curve at its reference velocity.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct AngularLimits {
+    pub dtheta: AngularVelocity,
+    pub ddtheta: AngularAcceleration,
+    pub dddtheta: AngularJerk,
+}
+
+// Turns whose sine is smaller than this are regarded as U-turns.
+const SIN_EPSILON: f32 = 1e-3;
+
+// Return the displacement of the left curve turning by `theta` at a unit reference velocity.
+fn unit_curve_displacement(theta: Angle, limits: &AngularLimits) -> (f32, f32) {
+    use uom::si::time::second;
+
+    let generator =
+        AngleStraightCalculatorGenerator::new(limits.dtheta, limits.ddtheta, limits.dddtheta);
+    let (calculator, t_end) = generator.generate(
+        Default::default(),
+        theta.abs(),
+        Default::default(),
+        Default::default(),
+    );
+    integrate_heading(
+        |t| calculator.calculate(Time::new::<second>(t)).x.value,
+        t_end.value,
+    )
+}
+
+// Return the pose of `exit` in the frame of `entry`.
+fn relative_pose(entry: &Pose, exit: &Pose) -> Pose {
+    let (sin, cos) = (entry.theta.value.sin(), entry.theta.value.cos());
+    let dx = exit.x - entry.x;
+    let dy = exit.y - entry.y;
+    Pose {
+        x: dx * cos + dy * sin,
+        y: -dx * sin + dy * cos,
+        theta: exit.theta - entry.theta,
+    }
+}
+
+impl SlalomParameters {
+    /// Solves parameters of the slalom from `entry` to `exit` turning by `theta`.
+    ///
+    /// The curve follows the angular profile with `limits` at `v_ref`
+    /// and the straights before and after it are solved from the poses.
+    pub fn solve(
+        theta: Angle,
+        entry: &Pose,
+        exit: &Pose,
+        v_ref: Velocity,
+        limits: &AngularLimits,
+    ) -> Result<Self, SlalomError> {
+        let exit = Self::normalize(theta, entry, exit)?;
+        let (dx, dy) = unit_curve_displacement(theta, limits);
+        Self::solve_left(theta, &exit, v_ref, limits, (dx, dy))
+    }
+
+    /// Solves parameters of the slalom from `entry` to `exit` with the maximum `v_ref`,
+    /// i.e. the largest curve, keeping both straights longer than `min_straight`.
+    pub fn solve_with_max_v_ref(
+        theta: Angle,
+        entry: &Pose,
+        exit: &Pose,
+        limits: &AngularLimits,
+        min_straight: Length,
+    ) -> Result<Self, SlalomError> {
+        use uom::si::time::second;
+
+        let exit = Self::normalize(theta, entry, exit)?;
+        let (sin, cos) = (exit.theta.value.sin(), exit.theta.value.cos());
+        let (dx, dy) = unit_curve_displacement(theta, limits);
+        let exit = Pose {
+            x: exit.x - min_straight * (1.0 + cos),
+            y: exit.y - min_straight * sin,
+            ..exit
+        };
+        // the curve is proportional to `v_ref`.
+        let mut v_ref = exit.y / Time::new::<second>(dy);
+        let coef = dx - dy * cos / sin;
+        if sin >= SIN_EPSILON && coef > 0.0 {
+            v_ref = v_ref.min((exit.x - exit.y * cos / sin) / Time::new::<second>(coef));
+        }
+        if v_ref.value <= 0.0 {
+            return Err(SlalomError::DoesNotFit);
+        }
+        let mut params = Self::solve_left(theta, &exit, v_ref, limits, (dx, dy))?;
+        params.l_start += min_straight;
+        params.x_curve_end += min_straight;
+        params.l_end += min_straight;
+        Ok(params)
+    }
+
+    // Return `exit` in the frame of `entry` mirrored to make a left turn.
+    fn normalize(theta: Angle, entry: &Pose, exit: &Pose) -> Result<Pose, SlalomError> {
+        let exit = relative_pose(entry, exit);
+        let diff = (exit.theta - theta).value.rem_euclid(2.0 * PI);
+        let abs = theta.value.abs();
+        if !(1e-3..=PI + 1e-3).contains(&abs) || diff.min(2.0 * PI - diff) > 1e-3 {
+            return Err(SlalomError::InvalidAngle);
+        }
+        Ok(Pose {
+            y: exit.y * theta.value.signum(),
+            theta: theta.abs(),
+            ..exit
+        })
+    }
+
+    // Solve straights of the left slalom to `exit`
+    // where the curve with a unit reference velocity moves by `unit_displacement`.
+    fn solve_left(
+        theta: Angle,
+        exit: &Pose,
+        v_ref: Velocity,
+        limits: &AngularLimits,
+        (dx, dy): (f32, f32),
+    ) -> Result<Self, SlalomError> {
+        use uom::si::{length::meter, time::second};
+
+        let (sin, cos) = (exit.theta.value.sin(), exit.theta.value.cos());
+        let (dx, dy) = (
+            v_ref * Time::new::<second>(dx),
+            v_ref * Time::new::<second>(dy),
+        );
+        let tolerance = Length::new::<meter>(1e-4);
+
+        let (l_start, l_end) = if sin < SIN_EPSILON {
+            // a U-turn can not absorb any lateral error with straights.
+            if (exit.y - dy).abs() > tolerance {
+                return Err(SlalomError::DoesNotFit);
+            }
+            let diff = exit.x - dx;
+            (diff, -diff)
+        } else {
+            let l_end = (exit.y - dy) / sin;
+            let l_start = exit.x - dx - l_end * cos;
+            if l_start < -tolerance || l_end < -tolerance {
+                return Err(SlalomError::DoesNotFit);
+            }
+            (l_start, l_end)
+        };
+        let l_start = l_start.max(Default::default());
+        let l_end = l_end.max(Default::default());
+        Ok(Self {
+            l_start,
+            l_end,
+            x_curve_end: l_start + dx,
+            y_curve_end: dy * theta.value.signum(),
+            theta,
+            v_ref,
+            dtheta: limits.dtheta,
+            ddtheta: limits.ddtheta,
+            dddtheta: limits.dddtheta,
+        })
+    }
+}
+
+fn max_velocity(a: Velocity, b: Velocity) -> Velocity {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
 pub struct SlalomGenerator {
     period: Time,
+    a_max: Acceleration,
+    j_max: Jerk,
     straight_generator: StraightGenerator,
 }
 
@@ -73,13 +342,29 @@ impl SlalomGenerator {
     pub fn new(period: Time, v_max: Velocity, a_max: Acceleration, j_max: Jerk) -> Self {
         Self {
             period,
+            a_max,
+            j_max,
             straight_generator: StraightGenerator::new(v_max, a_max, j_max, period),
         }
     }
 }
 
-pub type SlalomTrajectory =
-    Chain<Chain<StraightTrajectory, CurveTrajectory>, ShiftTrajectory<StraightTrajectory>>;
+pub type SlalomTrajectory = ChainTrajectory<
+    ChainTrajectory<StraightTrajectory, CurveTrajectory>,
+    ShiftTrajectory<StraightTrajectory>,
+>;
+
+pub type SSlalomTrajectory = ChainTrajectory<SlalomTrajectory, ShiftTrajectory<SlalomTrajectory>>;
+
+pub type AcceleratingSlalomTrajectory = ChainTrajectory<
+    ChainTrajectory<StraightTrajectory, AcceleratingCurveTrajectory>,
+    ShiftTrajectory<StraightTrajectory>,
+>;
+
+pub type ClothoidSlalomTrajectory = ChainTrajectory<
+    ChainTrajectory<StraightTrajectory, ClothoidTrajectory>,
+    ShiftTrajectory<StraightTrajectory>,
+>;
 
 impl SlalomGenerator {
     pub fn generate_constant_slalom(
@@ -107,7 +392,7 @@ impl SlalomGenerator {
             },
             StraightGenerator::generate_constant(params.l_end, v, self.period),
         );
-        straight1.chain(curve).chain(straight2)
+        ChainTrajectory::new(ChainTrajectory::new(straight1, curve), straight2)
     }
 
     pub fn generate_slalom_with_terminal_velocity(
@@ -143,11 +428,248 @@ impl SlalomGenerator {
             end_straight,
         );
         (
-            start_straight.chain(curve).chain(end_straight),
+            ChainTrajectory::new(ChainTrajectory::new(start_straight, curve), end_straight),
             terminal_velocity,
         )
     }
 
+    /// Generates an S-turn such as [`SlalomKind::FastRunKojima`].
+    ///
+    /// `params` describes the first half of the turn. The second half is the point
+    /// reflection of the first one about its end pose.
+    pub fn generate_s_slalom_with_terminal_velocity(
+        &self,
+        params: SlalomParameters,
+        v_start: Velocity,
+        v_middle: Velocity,
+        v_end: Velocity,
+    ) -> (SSlalomTrajectory, Velocity) {
+        let pose = params.end_pose();
+        let reflected = params.reflected();
+        let (first, middle_velocity) =
+            self.generate_slalom_with_terminal_velocity(params, v_start, v_middle, v_middle);
+        let (second, terminal_velocity) = self.generate_slalom_with_terminal_velocity(
+            reflected,
+            middle_velocity,
+            middle_velocity,
+            v_end,
+        );
+        (
+            ChainTrajectory::new(first, ShiftTrajectory::new(pose, second)),
+            terminal_velocity,
+        )
+    }
+
+    /// Generates a slalom whose velocity changes during the curve.
+    ///
+    /// The robot accelerates from `v_start` to `v_middle` on the first straight
+    /// and starts to accelerate or brake toward `v_end` at the entrance of the curve.
+    /// The path is the same as the one of [`generate_constant_slalom`](Self::generate_constant_slalom).
+    /// Returns the trajectory and the terminal velocity reachable within the limits.
+    pub fn generate_accelerating_slalom(
+        &self,
+        params: SlalomParameters,
+        v_start: Velocity,
+        v_middle: Velocity,
+        v_end: Velocity,
+    ) -> (AcceleratingSlalomTrajectory, Velocity) {
+        let (start_straight, middle_velocity) = self
+            .straight_generator
+            .generate_with_terminal_velocity(params.l_start, v_start, v_middle);
+        let (angle_calculator, length_calculator, t_end, curve_velocity) =
+            self.generate_accelerating_curve(&params, middle_velocity, v_end);
+        let curve = AcceleratingCurveTrajectory {
+            angle_calculator,
+            length_calculator,
+            t: Default::default(),
+            t_end,
+            period: self.period,
+            x_start: params.l_start,
+            x: params.l_start,
+            y: Default::default(),
+            v_ref: params.v_ref,
+        };
+        let (end_straight, terminal_velocity) = self
+            .straight_generator
+            .generate_with_terminal_velocity(params.l_end, curve_velocity, v_end);
+        let end_straight = ShiftTrajectory::new(
+            Pose {
+                x: params.x_curve_end,
+                y: params.y_curve_end,
+                theta: params.theta,
+            },
+            end_straight,
+        );
+        (
+            ChainTrajectory::new(ChainTrajectory::new(start_straight, curve), end_straight),
+            terminal_velocity,
+        )
+    }
+
+    // Return calculators of the angle and the distance of the accelerating curve,
+    // its duration and its terminal velocity.
+    fn generate_accelerating_curve(
+        &self,
+        params: &SlalomParameters,
+        v_start: Velocity,
+        v_end: Velocity,
+    ) -> (
+        AngleOverallCalculator,
+        LengthOverallCalculator,
+        Time,
+        Velocity,
+    ) {
+        let angle_generator =
+            AngleStraightCalculatorGenerator::new(params.dtheta, params.ddtheta, params.dddtheta);
+        let (angle_calculator, t_ref) = angle_generator.generate(
+            Default::default(),
+            params.theta,
+            Default::default(),
+            Default::default(),
+        );
+        // the velocity does not exceed the larger one of the both ends in the curve.
+        let v_max = max_velocity(v_start, v_end);
+        let (length_calculator, t_end, curve_velocity) =
+            LengthStraightCalculatorGenerator::new(v_max, self.a_max, self.j_max)
+                .generate_with_terminal_velocity(
+                    Default::default(),
+                    params.v_ref * t_ref,
+                    v_start,
+                    v_end,
+                );
+        (angle_calculator, length_calculator, t_end, curve_velocity)
+    }
+
+    /// Returns the summary of the trajectory which
+    /// [`generate_slalom_with_terminal_velocity`](Self::generate_slalom_with_terminal_velocity)
+    /// generates.
+    ///
+    /// This also describes [`generate_constant_slalom`](Self::generate_constant_slalom)
+    /// when all velocities are the same.
+    pub fn summarize_slalom(
+        &self,
+        params: &SlalomParameters,
+        v_start: Velocity,
+        v_middle: Velocity,
+        v_end: Velocity,
+    ) -> TrajectorySummary {
+        let start = self
+            .straight_generator
+            .summarize(params.l_start, v_start, v_middle);
+        let middle_velocity = start.end_velocity;
+        let end = self
+            .straight_generator
+            .summarize(params.l_end, middle_velocity, v_end);
+        TrajectorySummary {
+            duration: start.duration + params.curve_duration(middle_velocity) + end.duration,
+            end_velocity: end.end_velocity,
+            peak_velocity: max_velocity(start.peak_velocity, end.peak_velocity),
+            end_pose: params.end_pose(),
+        }
+    }
+
+    /// Returns the summary of the trajectory which
+    /// [`generate_s_slalom_with_terminal_velocity`](Self::generate_s_slalom_with_terminal_velocity)
+    /// generates.
+    pub fn summarize_s_slalom(
+        &self,
+        params: &SlalomParameters,
+        v_start: Velocity,
+        v_middle: Velocity,
+        v_end: Velocity,
+    ) -> TrajectorySummary {
+        let first = self.summarize_slalom(params, v_start, v_middle, v_middle);
+        let second = self.summarize_slalom(
+            &params.reflected(),
+            first.end_velocity,
+            first.end_velocity,
+            v_end,
+        );
+        let pose = first.end_pose;
+        let (sin, cos) = (pose.theta.value.sin(), pose.theta.value.cos());
+        let local = second.end_pose;
+        TrajectorySummary {
+            duration: first.duration + second.duration,
+            end_velocity: second.end_velocity,
+            peak_velocity: max_velocity(first.peak_velocity, second.peak_velocity),
+            end_pose: Pose {
+                x: pose.x + local.x * cos - local.y * sin,
+                y: pose.y + local.x * sin + local.y * cos,
+                theta: pose.theta + local.theta,
+            },
+        }
+    }
+
+    /// Returns the summary of the trajectory which
+    /// [`generate_accelerating_slalom`](Self::generate_accelerating_slalom) generates.
+    pub fn summarize_accelerating_slalom(
+        &self,
+        params: &SlalomParameters,
+        v_start: Velocity,
+        v_middle: Velocity,
+        v_end: Velocity,
+    ) -> TrajectorySummary {
+        let start = self
+            .straight_generator
+            .summarize(params.l_start, v_start, v_middle);
+        let (_, _, t_curve, curve_velocity) =
+            self.generate_accelerating_curve(params, start.end_velocity, v_end);
+        let end = self
+            .straight_generator
+            .summarize(params.l_end, curve_velocity, v_end);
+        TrajectorySummary {
+            duration: start.duration + t_curve + end.duration,
+            end_velocity: end.end_velocity,
+            peak_velocity: max_velocity(
+                max_velocity(start.peak_velocity, curve_velocity),
+                end.peak_velocity,
+            ),
+            end_pose: params.end_pose(),
+        }
+    }
+
+    /// Returns the summary of the trajectory which
+    /// [`generate_clothoid_slalom`](Self::generate_clothoid_slalom) generates.
+    pub fn summarize_clothoid_slalom(
+        &self,
+        slalom: &ClothoidSlalom,
+        v: Velocity,
+    ) -> TrajectorySummary {
+        let (dx, dy) = slalom.turn.displacement();
+        let theta = slalom.turn.theta;
+        let (sin, cos) = (theta.value.sin(), theta.value.cos());
+        TrajectorySummary {
+            duration: (slalom.l_start + slalom.turn.length() + slalom.l_end) / v,
+            end_velocity: v,
+            peak_velocity: v,
+            end_pose: Pose {
+                x: slalom.l_start + dx + slalom.l_end * cos,
+                y: dy + slalom.l_end * sin,
+                theta,
+            },
+        }
+    }
+
+    /// Generates a slalom which exactly follows the given clothoids at a constant velocity.
+    pub fn generate_clothoid_slalom(
+        &self,
+        slalom: &ClothoidSlalom,
+        v: Velocity,
+    ) -> ClothoidSlalomTrajectory {
+        let (dx, dy) = slalom.turn.displacement();
+        let straight1 = StraightGenerator::generate_constant(slalom.l_start, v, self.period);
+        let curve = ClothoidTrajectory::new(slalom.turn, slalom.l_start, v, self.period);
+        let straight2 = ShiftTrajectory::new(
+            Pose {
+                x: slalom.l_start + dx,
+                y: dy,
+                theta: slalom.turn.theta,
+            },
+            StraightGenerator::generate_constant(slalom.l_end, v, self.period),
+        );
+        ChainTrajectory::new(ChainTrajectory::new(straight1, curve), straight2)
+    }
+
     #[inline]
     #[allow(clippy::too_many_arguments)]
     fn generate_curve(
@@ -183,6 +705,8 @@ pub struct CurveTrajectory {
     t: Time,
     t_end: Time,
     period: Time,
+    x_start: Length,
+    y_start: Length,
     x: Length,
     y: Length,
     v: Velocity,
@@ -195,6 +719,8 @@ impl Clone for CurveTrajectory {
             t: self.t,
             t_end: self.t_end,
             period: self.period,
+            x_start: self.x_start,
+            y_start: self.y_start,
             x: self.x,
             y: self.y,
             v: self.v,
@@ -216,11 +742,64 @@ impl CurveTrajectory {
             t: Default::default(),
             t_end,
             period,
+            x_start,
+            y_start,
             x: x_start,
             y: y_start,
             v,
         }
     }
+
+    fn position_at(&self, t: Time) -> (Length, Length) {
+        use uom::si::time::second;
+
+        let t = if t > self.t_end { self.t_end } else { t };
+        let (dx, dy) = integrate_heading(
+            |t| {
+                self.angle_calculator
+                    .calculate(Time::new::<second>(t))
+                    .x
+                    .value
+            },
+            t.value.max(0.0),
+        );
+        (
+            self.x_start + self.v * Time::new::<second>(dx),
+            self.y_start + self.v * Time::new::<second>(dy),
+        )
+    }
+
+    fn target(&self, t: Time, x: Length, y: Length) -> Target {
+        let target = self.angle_calculator.calculate(t);
+        let sin_theta = target.x.value.sin();
+        let cos_theta = target.x.value.cos();
+
+        let vx = self.v * cos_theta;
+        let ax = -self.v * sin_theta * target.v;
+        let jx = -self.v
+            * (cos_theta * AngularAcceleration::from(target.v * target.v) + sin_theta * target.a);
+
+        let vy = self.v * sin_theta;
+        let ay = self.v * cos_theta * target.v;
+        let jy = self.v
+            * (-sin_theta * AngularAcceleration::from(target.v * target.v) + cos_theta * target.a);
+
+        Target {
+            x: LengthTarget {
+                x,
+                v: vx,
+                a: ax,
+                j: jx,
+            },
+            y: LengthTarget {
+                x: y,
+                v: vy,
+                a: ay,
+                j: jy,
+            },
+            theta: target,
+        }
+    }
 }
 
 impl Iterator for CurveTrajectory {
@@ -252,64 +831,171 @@ impl Iterator for CurveTrajectory {
         self.x += self.v * self.period * delta_x;
         self.y += self.v * self.period * delta_y;
 
-        let target = self.angle_calculator.calculate(t);
-        let sin_theta = target.x.value.sin();
-        let cos_theta = target.x.value.cos();
+        Some(self.target(t, x, y))
+    }
+}
 
-        let vx = self.v * cos_theta;
-        let ax = -self.v * sin_theta * target.v;
-        let jx = -self.v
-            * (cos_theta * AngularAcceleration::from(target.v * target.v) + sin_theta * target.a);
+impl TimedTrajectory for CurveTrajectory {
+    fn duration(&self) -> Time {
+        self.t_end
+    }
 
-        let vy = self.v * sin_theta;
-        let ay = self.v * cos_theta * target.v;
-        let jy = self.v
-            * (-sin_theta * AngularAcceleration::from(target.v * target.v) + cos_theta * target.a);
+    fn evaluate(&self, t: Time) -> Target {
+        let (x, y) = self.position_at(t);
+        self.target(t, x, y)
+    }
+
+    fn seek(&mut self, t: Time) {
+        self.t = t;
+        let (x, y) = self.position_at(t);
+        self.x = x;
+        self.y = y;
+    }
+
+    fn next_time(&self) -> Time {
+        self.t
+    }
+}
 
-        Some(Target {
+/// A curve whose translational velocity changes.
+///
+/// The angle follows the profile at `v_ref` as a function of the travelled distance,
+/// so that the path does not depend on the velocity.
+#[derive(Clone)]
+pub struct AcceleratingCurveTrajectory {
+    angle_calculator: AngleOverallCalculator,
+    length_calculator: LengthOverallCalculator,
+    t: Time,
+    t_end: Time,
+    period: Time,
+    x_start: Length,
+    x: Length,
+    y: Length,
+    v_ref: Velocity,
+}
+
+impl AcceleratingCurveTrajectory {
+    fn angle(&self, distance: Length) -> AngleTarget {
+        self.angle_calculator.calculate(distance / self.v_ref)
+    }
+
+    fn position_at(&self, t: Time) -> (Length, Length) {
+        use uom::si::length::meter;
+
+        let distance = self.length_calculator.calculate(t).x;
+        let (dx, dy) = integrate_heading(
+            |s| self.angle(Length::new::<meter>(s)).x.value,
+            distance.value,
+        );
+        (
+            self.x_start + Length::new::<meter>(dx),
+            Length::new::<meter>(dy),
+        )
+    }
+
+    fn target(&self, t: Time, x: Length, y: Length) -> Target {
+        use uom::si::{
+            angular_acceleration::radian_per_second_squared, angular_jerk::radian_per_second_cubed,
+            angular_velocity::radian_per_second,
+        };
+
+        let length = self.length_calculator.calculate(t);
+        // derivatives of the angle with respect to the reference time
+        let reference = self.angle(length.x);
+        let (w, dw, ddw) = (reference.v.value, reference.a.value, reference.j.value);
+        // derivatives of the reference time
+        let v_ref = self.v_ref.value;
+        let (k, dk, ddk) = (
+            length.v.value / v_ref,
+            length.a.value / v_ref,
+            length.j.value / v_ref,
+        );
+        let omega = w * k;
+        let alpha = dw * k * k + w * dk;
+        let jerk = ddw * k * k * k + 3.0 * dw * k * dk + w * ddk;
+
+        let (v, a, j) = (length.v.value, length.a.value, length.j.value);
+        let (sin, cos) = (reference.x.value.sin(), reference.x.value.cos());
+        let target = |cos: f32, sin: f32| LengthTarget {
+            x: Default::default(),
+            v: Velocity::new::<meter_per_second>(v * cos),
+            a: Acceleration::new::<meter_per_second_squared>(a * cos - v * sin * omega),
+            j: Jerk::new::<meter_per_second_cubed>(
+                j * cos - 2.0 * a * sin * omega - v * (cos * omega * omega + sin * alpha),
+            ),
+        };
+        Target {
             x: LengthTarget {
                 x,
-                v: vx,
-                a: ax,
-                j: jx,
+                ..target(cos, sin)
             },
+            // y is x rotated by 90 degrees
             y: LengthTarget {
                 x: y,
-                v: vy,
-                a: ay,
-                j: jy,
+                ..target(sin, -cos)
             },
-            theta: target,
-        })
+            theta: AngleTarget {
+                x: reference.x,
+                v: AngularVelocity::new::<radian_per_second>(omega),
+                a: AngularAcceleration::new::<radian_per_second_squared>(alpha),
+                j: AngularJerk::new::<radian_per_second_cubed>(jerk),
+            },
+        }
     }
+}
+
+impl Iterator for AcceleratingCurveTrajectory {
+    type Item = Target;
 
-    #[cfg(nightly)]
-    fn advance_by(&mut self, n: usize) -> Result<(), usize> {
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.t > self.t_end {
+            return None;
+        }
         let t = self.t;
-        self.t += self.period * n as f32;
-
-        let update_pos = |this: &mut Self, dt: Time| {
-            //gauss legendre (s=3)
-            let cs = [0.112701654, 0.5, 0.88729835];
-            let bs = [0.2777778, 0.44444445, 0.2777778];
-            let mut delta_x = 0.0;
-            let mut delta_y = 0.0;
-            for i in 0..3 {
-                let (sin, cos) = M::sincos(this.angle_calculator.calculate(t + cs[i] * dt).x);
-                delta_x += bs[i] * cos;
-                delta_y += bs[i] * sin;
-            }
+        self.t += self.period;
 
-            this.x += this.v * dt * delta_x;
-            this.y += this.v * dt * delta_y;
-        };
-        if self.t >= self.t_end {
-            update_pos(self, self.t - self.t_end);
-            Err(n - ((self.t - self.t_end) / self.period).get::<uom::si::ratio::ratio>() as usize)
-        } else {
-            update_pos(self, self.period * n as f32);
-            Ok(())
+        let distance = self.length_calculator.calculate(t).x;
+        let next_distance = self.length_calculator.calculate(self.t).x;
+
+        //gauss legendre (s=2) over the travelled distance
+        let cs = [0.21132487, 0.7886751];
+        let bs = [0.5, 0.5];
+        let ds = next_distance - distance;
+        let mut delta_x = 0.0;
+        let mut delta_y = 0.0;
+        for i in 0..2 {
+            let angle = self.angle(distance + cs[i] * ds).x;
+            delta_x += bs[i] * angle.value.cos();
+            delta_y += bs[i] * angle.value.sin();
         }
+        let x = self.x;
+        let y = self.y;
+        self.x += ds * delta_x;
+        self.y += ds * delta_y;
+
+        Some(self.target(t, x, y))
+    }
+}
+
+impl TimedTrajectory for AcceleratingCurveTrajectory {
+    fn duration(&self) -> Time {
+        self.t_end
+    }
+
+    fn evaluate(&self, t: Time) -> Target {
+        let (x, y) = self.position_at(t);
+        self.target(t, x, y)
+    }
+
+    fn seek(&mut self, t: Time) {
+        self.t = t;
+        let (x, y) = self.position_at(t);
+        self.x = x;
+        self.y = y;
+    }
+
+    fn next_time(&self) -> Time {
+        self.t
     }
 }
 
@@ -323,6 +1009,8 @@ pub struct SlalomConfig {
     fast_run135_rev: [SlalomParameters; 2],
     fast_run180: [SlalomParameters; 2],
     fast_run_diagonal90: [SlalomParameters; 2],
+    fast_run_diagonal90_large: [SlalomParameters; 2],
+    fast_run_kojima: [SlalomParameters; 2],
 }
 
 impl Default for SlalomConfig {
@@ -440,9 +1128,107 @@ impl SlalomConfig {
                 90.0,
                 square_width,
             ),
+            fast_run_diagonal90_large: Self::create_params(
+                0.255_225_6,
+                0.255_225_6,
+                SQRT_2,
+                1.158_988,
+                7.0,
+                90.0,
+                square_width,
+            ),
+            fast_run_kojima: Self::create_params(
+                0.133_974_6,
+                0.0,
+                1.0,
+                0.5,
+                4.826_488,
+                60.0,
+                square_width,
+            ),
         }
     }
 
+    // A large angular jerk keeps slaloms derived from clothoids close to them.
+    const CLOTHOID_DDDTHETA: AngularJerk = AngularJerk {
+        value: 12000.0 * PI,
+        dimension: PhantomData,
+        units: PhantomData,
+    };
+
+    /// Derives parameters of all kinds of slaloms from [`ClothoidSlalom`]s.
+    ///
+    /// Clothoids make `clothoid_ratio` of each turn and every straight is longer than
+    /// `min_straight`. `v_ref` is chosen so that the peak angular velocity at `v_ref`
+    /// is the default one.
+    pub fn from_clothoids(
+        square_width: Length,
+        search_front_offset: Length,
+        clothoid_ratio: f32,
+        min_straight: Length,
+    ) -> Result<Self, SlalomError> {
+        Self::try_from_fn(|kind| {
+            let end = kind.end_pose(square_width, search_front_offset);
+            let slalom = ClothoidSlalom::solve(&end, clothoid_ratio, min_straight)?;
+            let v_ref = Self::DEFAULT_DTHETA.value * slalom.turn.radius.value;
+            Ok(slalom.parameters(
+                Velocity::new::<uom::si::velocity::meter_per_second>(v_ref),
+                Self::CLOTHOID_DDDTHETA,
+            ))
+        })
+    }
+
+    /// Solves parameters of all kinds of slaloms with the given angular limits.
+    ///
+    /// Each curve is the largest one keeping every straight longer than `min_straight`.
+    /// See [`SlalomParameters::solve_with_max_v_ref`].
+    pub fn from_limits(
+        square_width: Length,
+        search_front_offset: Length,
+        limits: &AngularLimits,
+        min_straight: Length,
+    ) -> Result<Self, SlalomError> {
+        Self::try_from_fn(|kind| {
+            let end = kind.end_pose(square_width, search_front_offset);
+            SlalomParameters::solve_with_max_v_ref(
+                end.theta,
+                &Default::default(),
+                &end,
+                limits,
+                min_straight,
+            )
+        })
+    }
+
+    // Build a config from parameters of left slaloms.
+    fn try_from_fn(
+        f: impl Fn(SlalomKind) -> Result<SlalomParameters, SlalomError>,
+    ) -> Result<Self, SlalomError> {
+        use SlalomKind::*;
+
+        let params = |kind| -> Result<[SlalomParameters; 2], SlalomError> {
+            let left = f(kind)?;
+            let right = SlalomParameters {
+                y_curve_end: -left.y_curve_end,
+                theta: -left.theta,
+                ..left.clone()
+            };
+            Ok([left, right])
+        };
+        Ok(Self {
+            search90: params(Search90)?,
+            fast_run45: params(FastRun45)?,
+            fast_run45_rev: params(FastRun45Rev)?,
+            fast_run90: params(FastRun90)?,
+            fast_run135: params(FastRun135)?,
+            fast_run135_rev: params(FastRun135Rev)?,
+            fast_run180: params(FastRun180)?,
+            fast_run_diagonal90: params(FastRunDiagonal90)?,
+            fast_run_diagonal90_large: params(FastRunDiagonal90Large)?,
+            fast_run_kojima: params(FastRunKojima)?,
+        })
+    }
+
     fn create_params(
         l_start: f32,
         l_end: f32,
@@ -490,6 +1276,8 @@ impl SlalomConfig {
             FastRun135Rev => self.fast_run135_rev[direction].clone(),
             FastRun180 => self.fast_run180[direction].clone(),
             FastRunDiagonal90 => self.fast_run_diagonal90[direction].clone(),
+            FastRunDiagonal90Large => self.fast_run_diagonal90_large[direction].clone(),
+            FastRunKojima => self.fast_run_kojima[direction].clone(),
         }
     }
 }
@@ -497,6 +1285,7 @@ impl SlalomConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::trajectory::sequence::Segment;
     use proptest::prelude::*;
     use uom::si::{
         acceleration::meter_per_second_squared,
@@ -512,6 +1301,7 @@ mod tests {
 
     const EPSILON: f32 = 1e-4;
 
+    #[allow(clippy::too_many_arguments)]
     fn get_curve_trajectory(
         tv: f32,
         ta: f32,
@@ -580,6 +1370,519 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_large_slalom_end_pose() {
+        use approx::assert_relative_eq;
+
+        let square_width = Length::new::<meter>(0.09);
+        let config = SlalomConfig::new(square_width, Default::default());
+        let generator = SlalomGenerator::new(
+            Time::new::<second>(0.001),
+            Velocity::new::<meter_per_second>(1.0),
+            Acceleration::new::<meter_per_second_squared>(1.0),
+            Jerk::new::<meter_per_second_cubed>(1.0),
+        );
+        let v = Velocity::new::<meter_per_second>(0.5);
+        let test_cases: [(SlalomKind, (f32, f32, f32)); 2] = [
+            (SlalomKind::FastRunDiagonal90Large, (SQRT_2, SQRT_2, 90.0)),
+            (SlalomKind::FastRunKojima, (2.0, 1.0, 0.0)),
+        ];
+        for (kind, (x, y, theta)) in test_cases {
+            for (dir, sign) in [(SlalomDirection::Left, 1.0), (SlalomDirection::Right, -1.0)] {
+                let params = config.parameters(kind, dir);
+                let target = if kind == SlalomKind::FastRunKojima {
+                    let (trajectory, _) =
+                        generator.generate_s_slalom_with_terminal_velocity(params, v, v, v);
+                    trajectory.last().unwrap()
+                } else {
+                    generator
+                        .generate_constant_slalom(params, v)
+                        .last()
+                        .unwrap()
+                };
+                assert_relative_eq!(
+                    target.x.x.get::<meter>(),
+                    (square_width * x).get::<meter>(),
+                    epsilon = 1e-3
+                );
+                assert_relative_eq!(
+                    target.y.x.get::<meter>(),
+                    (square_width * y * sign).get::<meter>(),
+                    epsilon = 1e-3
+                );
+                assert_relative_eq!(target.theta.x.get::<degree>(), theta * sign, epsilon = 1e-1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_slalom_config_from_clothoids() {
+        use approx::assert_relative_eq;
+
+        let generator = SlalomGenerator::new(
+            Time::new::<second>(0.001),
+            Velocity::new::<meter_per_second>(1.0),
+            Acceleration::new::<meter_per_second_squared>(1.0),
+            Jerk::new::<meter_per_second_cubed>(1.0),
+        );
+        let v = Velocity::new::<meter_per_second>(0.5);
+        for square_width in [0.09, 0.18] {
+            let square_width = Length::new::<meter>(square_width);
+            let offset = square_width / 9.0;
+            let config = SlalomConfig::from_clothoids(
+                square_width,
+                offset,
+                0.5,
+                Length::new::<meter>(0.005),
+            )
+            .unwrap();
+            for kind in SlalomKind::ALL {
+                let end = kind.end_pose(square_width, offset);
+                for (dir, sign) in [(SlalomDirection::Left, 1.0), (SlalomDirection::Right, -1.0)] {
+                    let target = generator
+                        .generate_constant_slalom(config.parameters(kind, dir), v)
+                        .last()
+                        .unwrap();
+                    assert_relative_eq!(
+                        target.x.x.get::<meter>(),
+                        end.x.get::<meter>(),
+                        epsilon = 1e-3
+                    );
+                    assert_relative_eq!(
+                        target.y.x.get::<meter>(),
+                        sign * end.y.get::<meter>(),
+                        epsilon = 1e-3
+                    );
+                    assert_relative_eq!(
+                        target.theta.x.get::<degree>(),
+                        sign * end.theta.get::<degree>(),
+                        epsilon = 0.5
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_reproduces_config() {
+        use approx::assert_relative_eq;
+
+        let square_width = Length::new::<meter>(0.09);
+        let config = SlalomConfig::new(square_width, Default::default());
+        // U-turns have no unique split of straights and are checked in other tests.
+        for kind in SlalomKind::ALL
+            .into_iter()
+            .filter(|kind| *kind != SlalomKind::FastRun180)
+        {
+            let expected = config.parameters(kind, SlalomDirection::Left);
+            let limits = AngularLimits {
+                dtheta: expected.dtheta,
+                ddtheta: expected.ddtheta,
+                dddtheta: expected.dddtheta,
+            };
+            let end = kind.end_pose(square_width, Default::default());
+            let params = SlalomParameters::solve(
+                expected.theta,
+                &Pose::default(),
+                &end,
+                expected.v_ref,
+                &limits,
+            )
+            .unwrap();
+            for (actual, expected) in [
+                (params.l_start, expected.l_start),
+                (params.l_end, expected.l_end),
+                (params.x_curve_end, expected.x_curve_end),
+                (params.y_curve_end, expected.y_curve_end),
+            ] {
+                assert_relative_eq!(
+                    actual.get::<meter>(),
+                    expected.get::<meter>(),
+                    epsilon = 1e-3
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_does_not_fit() {
+        let square_width = Length::new::<meter>(0.09);
+        let config = SlalomConfig::new(square_width, Default::default());
+        let expected = config.parameters(SlalomKind::FastRun90, SlalomDirection::Left);
+        let limits = AngularLimits {
+            dtheta: expected.dtheta,
+            ddtheta: expected.ddtheta,
+            dddtheta: expected.dddtheta,
+        };
+        let end = SlalomKind::FastRun90.end_pose(square_width, Default::default());
+        assert_eq!(
+            SlalomParameters::solve(
+                expected.theta,
+                &Pose::default(),
+                &end,
+                expected.v_ref * 2.0,
+                &limits,
+            ),
+            Err(SlalomError::DoesNotFit)
+        );
+        assert_eq!(
+            SlalomParameters::solve(
+                -expected.theta,
+                &Pose::default(),
+                &end,
+                expected.v_ref,
+                &limits,
+            ),
+            Err(SlalomError::InvalidAngle)
+        );
+    }
+
+    #[test]
+    fn test_solve_with_max_v_ref() {
+        use approx::assert_relative_eq;
+
+        let generator = SlalomGenerator::new(
+            Time::new::<second>(0.001),
+            Velocity::new::<meter_per_second>(1.0),
+            Acceleration::new::<meter_per_second_squared>(1.0),
+            Jerk::new::<meter_per_second_cubed>(1.0),
+        );
+        let v = Velocity::new::<meter_per_second>(0.5);
+        let limits = AngularLimits {
+            dtheta: SlalomConfig::DEFAULT_DTHETA,
+            ddtheta: SlalomConfig::DEFAULT_DDTHETA,
+            dddtheta: SlalomConfig::DEFAULT_DDDTHETA,
+        };
+        let min_straight = Length::new::<meter>(0.005);
+        let entry = Pose {
+            x: Length::new::<meter>(0.045),
+            y: Length::new::<meter>(0.09),
+            theta: Angle::new::<degree>(90.0),
+        };
+        // the exit pose and the turn angle
+        let test_cases = [
+            (0.0, 0.18, 180.0, 90.0),
+            (0.005, 0.225, 135.0, 45.0),
+            (0.135, 0.18, 0.0, -90.0),
+            (-0.045, 0.18, -135.0, 135.0),
+            (-0.045, 0.12, -90.0, 180.0),
+        ];
+        for (x, y, theta, turn) in test_cases {
+            let exit = Pose {
+                x: Length::new::<meter>(x),
+                y: Length::new::<meter>(y),
+                theta: Angle::new::<degree>(theta),
+            };
+            let params = SlalomParameters::solve_with_max_v_ref(
+                Angle::new::<degree>(turn),
+                &entry,
+                &exit,
+                &limits,
+                min_straight,
+            )
+            .unwrap();
+            assert!(params.l_start >= min_straight * 0.99, "{:?}", params);
+            assert!(params.l_end >= min_straight * 0.99, "{:?}", params);
+
+            // the generated trajectory starts at the origin heading to x.
+            let target = generator
+                .generate_constant_slalom(params, v)
+                .last()
+                .unwrap();
+            let expected = relative_pose(&entry, &exit);
+            assert_relative_eq!(
+                target.x.x.get::<meter>(),
+                expected.x.get::<meter>(),
+                epsilon = 1e-3
+            );
+            assert_relative_eq!(
+                target.y.x.get::<meter>(),
+                expected.y.get::<meter>(),
+                epsilon = 1e-3
+            );
+        }
+    }
+
+    #[test]
+    fn test_accelerating_slalom() {
+        use approx::assert_relative_eq;
+
+        let period = Time::new::<second>(0.001);
+        let generator = SlalomGenerator::new(
+            period,
+            Velocity::new::<meter_per_second>(3.0),
+            Acceleration::new::<meter_per_second_squared>(10.0),
+            Jerk::new::<meter_per_second_cubed>(200.0),
+        );
+        let config = SlalomConfig::default();
+        let v_middle = Velocity::new::<meter_per_second>(0.5);
+        let test_cases = [
+            (
+                SlalomKind::FastRun180,
+                Velocity::new::<meter_per_second>(0.3),
+            ),
+            (
+                SlalomKind::FastRun45,
+                Velocity::new::<meter_per_second>(0.8),
+            ),
+            (SlalomKind::FastRun90, v_middle),
+        ];
+        for (kind, v_end) in test_cases {
+            for dir in [SlalomDirection::Left, SlalomDirection::Right] {
+                let params = config.parameters(kind, dir);
+                let expected = generator
+                    .generate_constant_slalom(params.clone(), v_middle)
+                    .last()
+                    .unwrap();
+                let (trajectory, terminal_velocity) =
+                    generator.generate_accelerating_slalom(params, v_middle, v_middle, v_end);
+                assert_relative_eq!(
+                    terminal_velocity.get::<meter_per_second>(),
+                    v_end.get::<meter_per_second>(),
+                    epsilon = 1e-3
+                );
+                let targets = trajectory.collect::<std::vec::Vec<_>>();
+                let last = targets.last().unwrap();
+                assert_relative_eq!(
+                    last.x.x.get::<meter>(),
+                    expected.x.x.get::<meter>(),
+                    epsilon = 1e-3
+                );
+                assert_relative_eq!(
+                    last.y.x.get::<meter>(),
+                    expected.y.x.get::<meter>(),
+                    epsilon = 1e-3
+                );
+                assert_relative_eq!(
+                    last.theta.x.get::<degree>(),
+                    expected.theta.x.get::<degree>(),
+                    epsilon = 1e-1
+                );
+
+                // every derivative is consistent with the next one in the curve.
+                let dt = period.get::<second>();
+                let mut jerk_mismatches = 0;
+                for pair in targets.windows(2) {
+                    let (prev, next) = (&pair[0], &pair[1]);
+                    if prev.theta.v.value == 0.0 || next.theta.v.value == 0.0 {
+                        continue;
+                    }
+                    for (prev, next) in [(prev.x, next.x), (prev.y, next.y)] {
+                        assert_relative_eq!(
+                            (next.x - prev.x).get::<meter>() / dt,
+                            (next.v + prev.v).get::<meter_per_second>() / 2.0,
+                            epsilon = 1e-2
+                        );
+                        assert_relative_eq!(
+                            (next.v - prev.v).get::<meter_per_second>() / dt,
+                            (next.a + prev.a).get::<meter_per_second_squared>() / 2.0,
+                            epsilon = 0.2
+                        );
+                        let diff = (next.a - prev.a).get::<meter_per_second_squared>() / dt
+                            - (next.j + prev.j).get::<meter_per_second_cubed>() / 2.0;
+                        if diff.abs() > 5.0 {
+                            jerk_mismatches += 1;
+                        }
+                    }
+                    assert_relative_eq!(
+                        (next.theta.x - prev.theta.x).get::<radian>() / dt,
+                        (next.theta.v + prev.theta.v).get::<radian_per_second>() / 2.0,
+                        epsilon = 1e-1
+                    );
+                    assert_relative_eq!(
+                        (next.theta.v - prev.theta.v).get::<radian_per_second>() / dt,
+                        (next.theta.a + prev.theta.a).get::<radian_per_second_squared>() / 2.0,
+                        epsilon = 5.0
+                    );
+                }
+                // jerks jump only at switching points of the profiles.
+                assert!(jerk_mismatches < 10, "{:?}: {}", kind, jerk_mismatches);
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate_slalom() {
+        use approx::assert_relative_eq;
+
+        let period = Time::new::<second>(0.001);
+        let generator = SlalomGenerator::new(
+            period,
+            Velocity::new::<meter_per_second>(3.0),
+            Acceleration::new::<meter_per_second_squared>(10.0),
+            Jerk::new::<meter_per_second_cubed>(200.0),
+        );
+        let config = SlalomConfig::default();
+        let v = Velocity::new::<meter_per_second>(0.5);
+        let assert_target_eq = |left: Target, right: Target| {
+            assert_relative_eq!(left.x.x.value, right.x.x.value, epsilon = 1e-4);
+            assert_relative_eq!(left.y.x.value, right.y.x.value, epsilon = 1e-4);
+            assert_relative_eq!(left.theta.x.value, right.theta.x.value, epsilon = 1e-4);
+            assert_relative_eq!(left.x.v.value, right.x.v.value, epsilon = 1e-4);
+            assert_relative_eq!(left.theta.v.value, right.theta.v.value, epsilon = 1e-3);
+        };
+
+        // a single curve is evaluated at the same time as the iteration.
+        let curve = get_curve_trajectory(
+            3.0 * PI,
+            36.0 * PI,
+            1200.0 * PI,
+            0.24,
+            0.001,
+            0.3,
+            0.0,
+            0.0,
+            0.0,
+            90.0,
+        );
+        for (i, target) in curve.clone().enumerate() {
+            assert_target_eq(curve.evaluate(period * i as f32), target);
+        }
+
+        for kind in [SlalomKind::Search90, SlalomKind::FastRun135] {
+            let params = config.parameters(kind, SlalomDirection::Right);
+            let (accelerating, _) =
+                generator.generate_accelerating_slalom(params.clone(), v, v, v * 2.0);
+            let (s_slalom, _) = generator.generate_s_slalom_with_terminal_velocity(
+                config.parameters(SlalomKind::FastRunKojima, SlalomDirection::Left),
+                v,
+                v,
+                v,
+            );
+            let segments: [Segment; 3] = [
+                generator.generate_constant_slalom(params, v).into(),
+                accelerating.into(),
+                s_slalom.into(),
+            ];
+            for segment in segments {
+                let last = segment.clone().last().unwrap();
+                let end = segment.end_pose();
+                assert_relative_eq!(end.x.value, last.x.x.value, epsilon = 1e-3);
+                assert_relative_eq!(end.y.value, last.y.x.value, epsilon = 1e-3);
+                assert_relative_eq!(end.theta.value, last.theta.x.value, epsilon = 1e-2);
+
+                // seeking restarts the iteration from the given time.
+                let t = segment.duration() / 2.0;
+                let mut seeked = segment.clone();
+                seeked.seek(t);
+                let target = seeked.next().unwrap();
+                assert_target_eq(target, segment.evaluate(t));
+                let rest = seeked.count() as f32;
+                assert_relative_eq!(
+                    rest,
+                    (segment.duration() - t).get::<second>() / period.get::<second>(),
+                    epsilon = 1.0
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_summarize_slalom() {
+        use approx::assert_relative_eq;
+
+        let period = Time::new::<second>(0.001);
+        let generator = SlalomGenerator::new(
+            period,
+            Velocity::new::<meter_per_second>(3.0),
+            Acceleration::new::<meter_per_second_squared>(10.0),
+            Jerk::new::<meter_per_second_cubed>(200.0),
+        );
+        let config = SlalomConfig::default();
+        let v_start = Velocity::new::<meter_per_second>(0.3);
+        let v_middle = Velocity::new::<meter_per_second>(0.6);
+        let v_end = Velocity::new::<meter_per_second>(1.0);
+        let kojima = config.parameters(SlalomKind::FastRunKojima, SlalomDirection::Right);
+        let params = config.parameters(SlalomKind::FastRun90, SlalomDirection::Left);
+        let (slalom, slalom_velocity) = generator.generate_slalom_with_terminal_velocity(
+            params.clone(),
+            v_start,
+            v_middle,
+            v_end,
+        );
+        let (s_slalom, s_slalom_velocity) = generator.generate_s_slalom_with_terminal_velocity(
+            kojima.clone(),
+            v_start,
+            v_middle,
+            v_end,
+        );
+        let (accelerating, accelerating_velocity) =
+            generator.generate_accelerating_slalom(params.clone(), v_start, v_middle, v_end);
+        let clothoid = ClothoidSlalom::solve(
+            &SlalomKind::FastRun135.end_pose(Length::new::<meter>(0.09), Default::default()),
+            0.5,
+            Length::new::<meter>(0.01),
+        )
+        .unwrap();
+        let test_cases: [(TrajectorySummary, Segment, Velocity); 4] = [
+            (
+                generator.summarize_slalom(&params, v_start, v_middle, v_end),
+                slalom.into(),
+                slalom_velocity,
+            ),
+            (
+                generator.summarize_s_slalom(&kojima, v_start, v_middle, v_end),
+                s_slalom.into(),
+                s_slalom_velocity,
+            ),
+            (
+                generator.summarize_accelerating_slalom(&params, v_start, v_middle, v_end),
+                accelerating.into(),
+                accelerating_velocity,
+            ),
+            (
+                generator.summarize_clothoid_slalom(&clothoid, v_middle),
+                generator
+                    .generate_clothoid_slalom(&clothoid, v_middle)
+                    .into(),
+                v_middle,
+            ),
+        ];
+        for (summary, segment, terminal_velocity) in test_cases {
+            assert_relative_eq!(
+                summary.duration.get::<second>(),
+                segment.duration().get::<second>(),
+                epsilon = 1e-4
+            );
+            assert_eq!(summary.end_velocity, terminal_velocity);
+            let end = segment.end_pose();
+            assert_relative_eq!(summary.end_pose.x.value, end.x.value, epsilon = 1e-3);
+            assert_relative_eq!(summary.end_pose.y.value, end.y.value, epsilon = 1e-3);
+            assert_relative_eq!(
+                summary.end_pose.theta.value,
+                end.theta.value,
+                epsilon = 1e-3
+            );
+            for target in segment {
+                let v = target.x.v * target.theta.x.value.cos()
+                    + target.y.v * target.theta.x.value.sin();
+                assert!(v.value <= summary.peak_velocity.value + 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_slalom_config_from_limits() {
+        let square_width = Length::new::<meter>(0.09);
+        let limits = AngularLimits {
+            dtheta: SlalomConfig::DEFAULT_DTHETA,
+            ddtheta: SlalomConfig::DEFAULT_DDTHETA,
+            dddtheta: SlalomConfig::DEFAULT_DDDTHETA,
+        };
+        let config = SlalomConfig::from_limits(
+            square_width,
+            Default::default(),
+            &limits,
+            Length::new::<meter>(0.005),
+        )
+        .unwrap();
+        let left = config.parameters(SlalomKind::FastRun90, SlalomDirection::Left);
+        let right = config.parameters(SlalomKind::FastRun90, SlalomDirection::Right);
+        assert_eq!(left.theta, -right.theta);
+        assert_eq!(left.y_curve_end, -right.y_curve_end);
+        assert!(left.v_ref.value > 0.0);
+    }
+
     proptest! {
         #[test]
         fn test_curve_generator(
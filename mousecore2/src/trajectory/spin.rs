@@ -1,8 +1,16 @@
-use uom::si::f32::{Angle, AngularAcceleration, AngularJerk, AngularVelocity, Time};
+use uom::si::{
+    angular_velocity::radian_per_second,
+    f32::{Angle, AngularAcceleration, AngularJerk, AngularVelocity, Length, Time, Velocity},
+};
 
 use crate::{
     control::Target,
-    trajectory::straight::{AngleOverallCalculator, AngleStraightCalculatorGenerator},
+    trajectory::{
+        slalom::CurveTrajectory,
+        straight::{AngleOverallCalculator, AngleStraightCalculatorGenerator},
+        TimedTrajectory, TrajectorySummary,
+    },
+    wall::Pose,
 };
 
 pub struct SpinGenerator {
@@ -40,6 +48,26 @@ impl SpinGenerator {
     }
 }
 
+impl SpinGenerator {
+    /// Returns the summary of the trajectory which [`generate`](Self::generate) generates.
+    pub fn summarize(&self, theta_distance: Angle) -> TrajectorySummary {
+        let (_, t_end) = self.function_generator.generate(
+            Default::default(),
+            theta_distance,
+            Default::default(),
+            Default::default(),
+        );
+        TrajectorySummary {
+            duration: t_end,
+            end_pose: Pose {
+                theta: theta_distance,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct SpinTrajectory {
     angle_calculator: AngleOverallCalculator,
@@ -68,19 +96,241 @@ impl Iterator for SpinTrajectory {
         }
         let t = self.t;
         self.t += self.period;
-        Some(Target {
+        Some(self.evaluate(t))
+    }
+}
+
+impl TimedTrajectory for SpinTrajectory {
+    fn duration(&self) -> Time {
+        self.t_end
+    }
+
+    fn evaluate(&self, t: Time) -> Target {
+        Target {
             theta: self.angle_calculator.calculate(t),
             ..Default::default()
-        })
+        }
+    }
+
+    fn seek(&mut self, t: Time) {
+        self.t = t;
+    }
+
+    fn next_time(&self) -> Time {
+        self.t
+    }
+}
+
+/// Error on generating pivot turns.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PivotError {
+    /// A wheel would exceed the maximum wheel velocity.
+    WheelSpeedExceeded,
+}
+
+/// A turn around the center of the robot, which may move forward at a constant velocity.
+pub type PivotTrajectory = CurveTrajectory;
+
+/// A generator of pivot turns by arbitrary angles.
+///
+/// Unlike [`SpinGenerator`], turns may start and end at non-zero angular velocities
+/// and the robot may creep along its heading during the turn.
+/// The angular velocity is limited so that both wheels stay within `max_wheel_velocity`.
+pub struct PivotGenerator {
+    max_angular_velocity: AngularVelocity,
+    max_angular_acceleration: AngularAcceleration,
+    max_angular_jerk: AngularJerk,
+    wheel_interval: Length,
+    max_wheel_velocity: Velocity,
+    period: Time,
+}
+
+impl PivotGenerator {
+    pub fn new(
+        max_angular_velocity: AngularVelocity,
+        max_angular_acceleration: AngularAcceleration,
+        max_angular_jerk: AngularJerk,
+        wheel_interval: Length,
+        max_wheel_velocity: Velocity,
+        period: Time,
+    ) -> Self {
+        Self {
+            max_angular_velocity,
+            max_angular_acceleration,
+            max_angular_jerk,
+            wheel_interval,
+            max_wheel_velocity,
+            period,
+        }
     }
+}
 
-    #[cfg(nightly)]
-    fn advance_by(&mut self, n: usize) -> Result<(), usize> {
-        self.t += self.period * n as f32;
-        if self.t < self.t_end {
-            Ok(())
+impl PivotGenerator {
+    /// Returns the maximum angular velocity which keeps both wheels within the limit
+    /// while moving at `v`.
+    pub fn max_angular_velocity(&self, v: Velocity) -> AngularVelocity {
+        let margin = self.max_wheel_velocity - v.abs();
+        let omega = AngularVelocity::new::<radian_per_second>(
+            2.0 * margin.value / self.wheel_interval.value,
+        );
+        if omega < self.max_angular_velocity {
+            omega
         } else {
-            Err(n - ((self.t - self.t_end) / self.period).get::<uom::si::ratio::ratio>() as usize)
+            self.max_angular_velocity
+        }
+    }
+
+    /// Generates a pivot turn by `theta_distance` while moving forward at `v`.
+    ///
+    /// `omega_start` and `omega_end` are non-negative angular velocities in the direction
+    /// of the turn. Returns the trajectory and the reachable terminal angular velocity.
+    pub fn generate(
+        &self,
+        theta_distance: Angle,
+        omega_start: AngularVelocity,
+        omega_end: AngularVelocity,
+        v: Velocity,
+    ) -> Result<(PivotTrajectory, AngularVelocity), PivotError> {
+        let omega_max = self.max_angular_velocity(v);
+        if omega_max.value <= 0.0 || omega_start > omega_max || omega_end > omega_max {
+            return Err(PivotError::WheelSpeedExceeded);
+        }
+        let (calculator, t_end, terminal_velocity) = AngleStraightCalculatorGenerator::new(
+            omega_max,
+            self.max_angular_acceleration,
+            self.max_angular_jerk,
+        )
+        .generate_with_terminal_velocity(
+            Default::default(),
+            theta_distance,
+            omega_start,
+            omega_end,
+        );
+        debug_assert!(calculator.peak_velocity() <= omega_max);
+        Ok((
+            CurveTrajectory::new(
+                calculator,
+                t_end,
+                self.period,
+                Default::default(),
+                Default::default(),
+                v,
+            ),
+            terminal_velocity,
+        ))
+    }
+
+    /// Returns the summary of the trajectory which [`generate`](Self::generate) generates.
+    pub fn summarize(
+        &self,
+        theta_distance: Angle,
+        omega_start: AngularVelocity,
+        omega_end: AngularVelocity,
+        v: Velocity,
+    ) -> Result<TrajectorySummary, PivotError> {
+        let (trajectory, _) = self.generate(theta_distance, omega_start, omega_end, v)?;
+        Ok(TrajectorySummary {
+            duration: trajectory.duration(),
+            end_velocity: v,
+            peak_velocity: v,
+            end_pose: trajectory.end_pose(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use uom::si::{
+        angle::degree, angular_acceleration::degree_per_second_squared,
+        angular_jerk::degree_per_second_cubed, angular_velocity::degree_per_second,
+        length::millimeter, time::second, velocity::meter_per_second,
+    };
+
+    use super::*;
+
+    fn generator() -> PivotGenerator {
+        PivotGenerator::new(
+            AngularVelocity::new::<degree_per_second>(1440.0),
+            AngularAcceleration::new::<degree_per_second_squared>(14400.0),
+            AngularJerk::new::<degree_per_second_cubed>(288000.0),
+            Length::new::<millimeter>(33.5),
+            Velocity::new::<meter_per_second>(0.5),
+            Time::new::<second>(0.001),
+        )
+    }
+
+    #[test]
+    fn test_pivot_within_wheel_speed() {
+        let generator = generator();
+        let test_cases = [
+            (90.0, 0.0, 0.0, 0.0),
+            (-45.0, 300.0, 0.0, 0.0),
+            (270.0, 0.0, 500.0, 0.05),
+            (-180.0, 200.0, 200.0, -0.05),
+        ];
+        for (theta, omega_start, omega_end, v) in test_cases {
+            let theta = Angle::new::<degree>(theta);
+            let v = Velocity::new::<meter_per_second>(v);
+            let (trajectory, terminal_velocity) = generator
+                .generate(
+                    theta,
+                    AngularVelocity::new::<degree_per_second>(omega_start),
+                    AngularVelocity::new::<degree_per_second>(omega_end),
+                    v,
+                )
+                .unwrap();
+            let first = trajectory.clone().next().unwrap();
+            assert_relative_eq!(
+                first.theta.v.get::<degree_per_second>().abs(),
+                omega_start,
+                epsilon = 1e-2
+            );
+            assert_relative_eq!(
+                terminal_velocity.get::<degree_per_second>(),
+                omega_end,
+                epsilon = 1e-2
+            );
+            let end = trajectory.end_pose();
+            assert_relative_eq!(
+                end.theta.get::<degree>(),
+                theta.get::<degree>(),
+                epsilon = 1e-2
+            );
+            for target in trajectory {
+                let v = target.x.v * target.theta.x.value.cos()
+                    + target.y.v * target.theta.x.value.sin();
+                let dv = generator.wheel_interval.value / 2.0 * target.theta.v.value;
+                for wheel in [v.value + dv, v.value - dv] {
+                    assert!(wheel.abs() <= 0.5 + 1e-4, "{}", wheel);
+                }
+            }
         }
     }
+
+    #[test]
+    fn test_pivot_wheel_speed_exceeded() {
+        let generator = generator();
+        let theta = Angle::new::<degree>(90.0);
+        assert_eq!(
+            generator
+                .generate(
+                    theta,
+                    Default::default(),
+                    Default::default(),
+                    Velocity::new::<meter_per_second>(0.6)
+                )
+                .err(),
+            Some(PivotError::WheelSpeedExceeded)
+        );
+        let omega_max = generator.max_angular_velocity(Velocity::new::<meter_per_second>(0.3));
+        assert!(generator
+            .generate(
+                theta,
+                omega_max * 1.01,
+                Default::default(),
+                Velocity::new::<meter_per_second>(0.3)
+            )
+            .is_err());
+    }
 }
@@ -1,37 +1,129 @@
+use core::f32::consts::PI;
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+use serde::{Deserialize, Serialize};
 use uom::si::{
+    acceleration::meter_per_second_squared,
     angle::radian,
+    angular_acceleration::radian_per_second_squared,
+    angular_velocity::radian_per_second,
     f32::{
         Acceleration, Angle, AngularAcceleration, AngularJerk, AngularVelocity, Jerk, Length, Time,
         Velocity,
     },
     length::meter,
     time::second,
+    velocity::meter_per_second,
 };
 
 use crate::{
     control::{AngleTarget, LengthTarget, Target},
-    trajectory::sqrt,
+    trajectory::{TimedTrajectory, TrajectorySummary},
+    wall::Pose,
 };
 
+/// The shape of acceleration and deceleration of straight profiles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProfileShape {
+    /// Acceleration changes stepwise without jerk limits.
+    Trapezoidal,
+    /// Acceleration changes linearly with the maximum jerk.
+    SCurve,
+    /// Jerk follows a half sine wave whose peak is the maximum jerk.
+    SinusoidalJerk,
+}
+
+impl Default for ProfileShape {
+    fn default() -> Self {
+        ProfileShape::SCurve
+    }
+}
+
+impl ProfileShape {
+    // Return the time to reach an acceleration relative to that of `SCurve`.
+    fn ramp_ratio(&self) -> f32 {
+        match self {
+            ProfileShape::Trapezoidal => 0.0,
+            ProfileShape::SCurve => 1.0,
+            ProfileShape::SinusoidalJerk => PI / 2.0,
+        }
+    }
+}
+
+// The maximum number of iterations of `bisect`, which usually converges earlier.
+const BISECTION_LIMIT: u8 = 64;
+
+// Return the larger root of `a x^2 + b x + c = 0` for positive `a`.
+fn larger_root(a: f32, b: f32, c: f32) -> Option<f32> {
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    Some((-b + discriminant.sqrt()) / (2.0 * a))
+}
+
+// Return the value next to the boundary on the side of `ok`, where `pred` holds.
+fn bisect(mut ok: f32, mut ng: f32, pred: impl Fn(f32) -> bool) -> f32 {
+    for _ in 0..BISECTION_LIMIT {
+        let mid = (ok + ng) / 2.0;
+        if mid == ok || mid == ng {
+            break;
+        }
+        if pred(mid) {
+            ok = mid;
+        } else {
+            ng = mid;
+        }
+    }
+    ok
+}
+
 macro_rules! impl_calculator_generator {
-    ($mod_name: ident, $t: ty, $dt: ty, $ddt: ty, $dddt: ty, $tunit: ty, $target: ident) => {
+    (
+        $mod_name: ident,
+        $t: ty,
+        $dt: ty,
+        $ddt: ty,
+        $dddt: ty,
+        $tunit: ty,
+        $dtunit: ty,
+        $ddtunit: ty,
+        $target: ident
+    ) => {
         mod $mod_name {
             use super::*;
 
             pub(crate) struct StraightCalculatorGenerator {
                 v_max: $dt,
-                a_max: $ddt,
-                j_max: $dddt,
+                a_accel: $ddt,
+                j_accel: $dddt,
+                a_decel: $ddt,
+                j_decel: $dddt,
+                shape: ProfileShape,
             }
 
             impl StraightCalculatorGenerator {
-                const LOOP_COUNT: u8 = 25;
-
                 pub fn new(v_max: $dt, a_max: $ddt, j_max: $dddt) -> Self {
+                    Self::with_profile(v_max, a_max, j_max, a_max, j_max, ProfileShape::SCurve)
+                }
+
+                #[allow(unused)]
+                pub fn with_profile(
+                    v_max: $dt,
+                    a_accel: $ddt,
+                    j_accel: $dddt,
+                    a_decel: $ddt,
+                    j_decel: $dddt,
+                    shape: ProfileShape,
+                ) -> Self {
                     Self {
                         v_max,
-                        a_max,
-                        j_max,
+                        a_accel,
+                        j_accel,
+                        a_decel,
+                        j_decel,
+                        shape,
                     }
                 }
             }
@@ -64,32 +156,12 @@ macro_rules! impl_calculator_generator {
                     } else {
                         (distance, 1.0f32)
                     };
-                    let v_reach = self.calculate_reachable_velocity(v_start, v_end, distance);
-                    let v_end = if v_start < v_end {
-                        if v_reach < v_end {
-                            v_reach
-                        } else {
-                            v_end
-                        }
-                    } else if v_reach > v_end {
-                        v_reach
-                    } else {
-                        v_end
-                    };
-                    let mut low = if v_start < v_end { v_end } else { v_start };
-                    let mut high = self.v_max;
-                    for _ in 0..Self::LOOP_COUNT {
-                        let mid = (low + high) / 2.0;
-                        let d = self.calculate_acceleration_distance(v_start, mid)
-                            + self.calculate_acceleration_distance(mid, v_end);
-                        if d <= distance {
-                            low = mid;
-                        } else {
-                            high = mid;
-                        }
-                    }
-
-                    let v_max = low;
+                    let v_end = self.calculate_reachable_velocity(v_start, v_end, distance);
+                    let v_max = <$dt>::new::<$dtunit>(self.calculate_peak_velocity(
+                        v_start.value,
+                        v_end.value,
+                        distance.value,
+                    ));
                     let v_end = if v_end < v_max { v_end } else { v_max };
 
                     let dist1 = self.calculate_acceleration_distance(v_start, v_max);
@@ -126,27 +198,82 @@ macro_rules! impl_calculator_generator {
                     )
                 }
 
+                // Return the acceleration and the jerk limiting the change from `v_start` to `v_end`.
+                fn limits(&self, v_start: f32, v_end: f32) -> (f32, f32) {
+                    if v_start <= v_end {
+                        (self.a_accel.value, self.j_accel.value)
+                    } else {
+                        (self.a_decel.value, self.j_decel.value)
+                    }
+                }
+
+                // Return the time to reach the peak acceleration, the time of the whole change
+                // and the peak acceleration from `v_start` to `v_end`.
+                //
+                // The solvers work on raw values in SI units.
+                fn transition(&self, v_start: f32, v_end: f32) -> (f32, f32, f32) {
+                    let (a, j) = self.limits(v_start, v_end);
+                    let r = self.shape.ramp_ratio();
+                    let dv = (v_end - v_start).abs();
+                    if dv >= r * a * a / j {
+                        let tr = r * a / j;
+                        (tr, dv / a + tr, a)
+                    } else {
+                        let a_p = (dv * j / r).sqrt();
+                        let tr = r * a_p / j;
+                        (tr, 2.0 * tr, a_p)
+                    }
+                }
+
+                fn distance(&self, v_start: f32, v_end: f32) -> f32 {
+                    let (_, t, _) = self.transition(v_start, v_end);
+                    (v_start + v_end) * t / 2.0
+                }
+
+                // Return the maximum velocity with which the profile fits in `distance`.
+                //
+                // The velocity is solved in closed form while both accelerations reach
+                // their limits, and by bisection otherwise.
+                fn calculate_peak_velocity(&self, v_start: f32, v_end: f32, distance: f32) -> f32 {
+                    let lower = v_start.max(v_end);
+                    let v_max = self.v_max.value;
+                    let total = |v| self.distance(v_start, v) + self.distance(v, v_end);
+                    if lower >= v_max || total(v_max) <= distance {
+                        return v_max.max(lower);
+                    }
+                    let r = self.shape.ramp_ratio();
+                    let (a1, j1) = (self.a_accel.value, self.j_accel.value);
+                    let (a2, j2) = (self.a_decel.value, self.j_decel.value);
+                    let root = larger_root(
+                        0.5 / a1 + 0.5 / a2,
+                        0.5 * r * (a1 / j1 + a2 / j2),
+                        0.5 * r * (v_start * a1 / j1 + v_end * a2 / j2)
+                            - 0.5 * (v_start * v_start / a1 + v_end * v_end / a2)
+                            - distance,
+                    );
+                    match root {
+                        Some(v)
+                            if v - v_start >= r * a1 * a1 / j1
+                                && v - v_end >= r * a2 * a2 / j2
+                                && v <= v_max =>
+                        {
+                            v
+                        }
+                        _ => bisect(lower, v_max, |v| total(v) <= distance),
+                    }
+                }
+
                 fn generate_acceleration(
                     &self,
                     x_start: $t,
                     v_start: $dt,
                     v_end: $dt,
                 ) -> (AccelerationCalculator, Time) {
-                    let tc = self.a_max / self.j_max;
-                    let vd = <$dt>::from(self.a_max * tc);
-                    let (t1, t2, t3) = if (v_end - v_start).abs() >= vd {
-                        let tm = (v_end - v_start).abs() / self.a_max - tc;
-                        (tc, tc + tm, 2.0 * tc + tm)
-                    } else {
-                        let td = sqrt((v_end - v_start).abs() / self.j_max);
-                        (td, td, 2.0 * td)
-                    };
-
-                    let (a_m, j_m) = if v_start <= v_end {
-                        (self.a_max, self.j_max)
-                    } else {
-                        (-self.a_max, -self.j_max)
-                    };
+                    let (tr, t_total, a_p) = self.transition(v_start.value, v_end.value);
+                    let a_p = if v_start <= v_end { a_p } else { -a_p };
+                    let t1 = Time::new::<second>(tr);
+                    let t3 = Time::new::<second>(t_total);
+                    let t2 = t3 - t1;
 
                     let distance = <$t>::from((v_start + v_end) * t3) / 2.0;
                     let x_end = x_start + distance;
@@ -157,25 +284,18 @@ macro_rules! impl_calculator_generator {
                             v_start,
                             x_end,
                             v_end,
-                            a_m,
-                            j_m,
+                            a_m: <$ddt>::new::<$ddtunit>(a_p),
                             t1,
                             t2,
                             t3,
+                            shape: self.shape,
                         },
                         t3,
                     )
                 }
 
                 fn calculate_acceleration_distance(&self, v_start: $dt, v_end: $dt) -> $t {
-                    let tc = self.a_max / self.j_max;
-                    let vd = <$dt>::from(self.a_max * tc);
-                    let t = if (v_end - v_start).abs() >= vd {
-                        (v_end - v_start).abs() / self.a_max + tc
-                    } else {
-                        2.0 * sqrt((v_end - v_start).abs() / self.j_max)
-                    };
-                    <$t>::from((v_start + v_end) * t / 2.0)
+                    <$t>::new::<$tunit>(self.distance(v_start.value, v_end.value))
                 }
 
                 pub fn generate_constant(
@@ -191,40 +311,54 @@ macro_rules! impl_calculator_generator {
                     (ConstantCalculator { t_end, x_start, v }, t_end)
                 }
 
-                //TODO: devise faster algorithm
+                /// Returns the velocity closest to `v_end` which is reachable from `v_start`
+                /// in `distance`.
+                ///
+                /// The velocity never exceeds `v_max`. It is solved in closed form while the
+                /// acceleration reaches its limit, and by bisection otherwise.
                 pub fn calculate_reachable_velocity(
                     &self,
                     v_start: $dt,
                     v_end: $dt,
                     distance: $t,
                 ) -> $dt {
-                    if v_start < v_end {
-                        let mut low = v_start;
-                        let mut high = self.v_max;
-                        for _ in 0..Self::LOOP_COUNT {
-                            let mid = (low + high) / 2.0;
-                            let d = self.calculate_acceleration_distance(v_start, mid);
-                            if d < distance {
-                                low = mid;
-                            } else {
-                                high = mid;
+                    let (v0, d) = (v_start.value, distance.value);
+                    let r = self.shape.ramp_ratio();
+                    let v = if v_start < v_end {
+                        let v1 = v_end.value.min(self.v_max.value);
+                        let (a, j) = (self.a_accel.value, self.j_accel.value);
+                        if self.distance(v0, v1) <= d {
+                            v1
+                        } else {
+                            let root = larger_root(
+                                0.5 / a,
+                                0.5 * r * a / j,
+                                0.5 * v0 * r * a / j - 0.5 * v0 * v0 / a - d,
+                            );
+                            match root {
+                                Some(v) if v - v0 >= r * a * a / j && v <= v1 => v,
+                                _ => bisect(v0, v1, |v| self.distance(v0, v) <= d),
                             }
                         }
-                        low
                     } else {
-                        let mut low = Default::default();
-                        let mut high = v_start;
-                        for _ in 0..Self::LOOP_COUNT {
-                            let mid = (low + high) / 2.0;
-                            let d = self.calculate_acceleration_distance(v_start, mid);
-                            if d < distance {
-                                high = mid;
-                            } else {
-                                low = mid;
+                        // the distance to brake is not monotonic in the terminal velocity.
+                        let v1 = v_end.value.max(0.0);
+                        let (a, j) = (self.a_decel.value, self.j_decel.value);
+                        if self.distance(v0, v1) <= d {
+                            v1
+                        } else {
+                            let root = larger_root(
+                                0.5 / a,
+                                -0.5 * r * a / j,
+                                d - 0.5 * v0 * v0 / a - 0.5 * v0 * r * a / j,
+                            );
+                            match root {
+                                Some(v) if v0 - v >= r * a * a / j && v >= v1 => v,
+                                _ => bisect(v0, v1, |v| self.distance(v0, v) <= d),
                             }
                         }
-                        low
-                    }
+                    };
+                    <$dt>::new::<$dtunit>(v)
                 }
             }
 
@@ -234,14 +368,47 @@ macro_rules! impl_calculator_generator {
                 v_start: $dt,
                 x_end: $t,
                 v_end: $dt,
+                // the peak acceleration, which is negative on deceleration
                 a_m: $ddt,
-                j_m: $dddt,
                 t1: Time,
                 t2: Time,
                 t3: Time,
+                shape: ProfileShape,
             }
 
             impl AccelerationCalculator {
+                // Return the jerk, the acceleration, and the changes of the velocity and the
+                // position at `t` after the acceleration starts to rise.
+                fn ramp(&self, t: Time) -> ($dddt, $ddt, $dt, $t) {
+                    if self.t1.get::<second>() <= 0.0 {
+                        return Default::default();
+                    }
+                    let j_m = <$dddt>::from(self.a_m / self.t1);
+                    match self.shape {
+                        ProfileShape::Trapezoidal => Default::default(),
+                        ProfileShape::SCurve => (
+                            j_m,
+                            <$ddt>::from(j_m * t),
+                            <$dt>::from(j_m * t * t / 2.0),
+                            <$t>::from(j_m * t * t * t / 6.0),
+                        ),
+                        ProfileShape::SinusoidalJerk => {
+                            let phase = PI * (t / self.t1).value;
+                            let (sin, cos) = (phase.sin(), phase.cos());
+                            (
+                                j_m * (PI / 2.0 * sin),
+                                self.a_m * ((1.0 - cos) / 2.0),
+                                <$dt>::from(self.a_m * (t - self.t1 * (sin / PI))) / 2.0,
+                                <$t>::from(
+                                    self.a_m
+                                        * (t * t / 2.0
+                                            - self.t1 * self.t1 * ((1.0 - cos) / (PI * PI))),
+                                ) / 2.0,
+                            )
+                        }
+                    }
+                }
+
                 fn calculate(&self, t: Time) -> $target {
                     if t.get::<second>() < 0.0 {
                         $target {
@@ -250,38 +417,35 @@ macro_rules! impl_calculator_generator {
                             a: Default::default(),
                             j: Default::default(),
                         }
-                    } else if t <= self.t1 {
+                    } else if t < self.t1 {
+                        let (j, a, dv, dx) = self.ramp(t);
                         $target {
-                            j: self.j_m,
-                            a: <$ddt>::from(self.j_m * t),
-                            v: self.v_start + <$dt>::from(self.j_m * t * t / 2.0),
-                            x: self.x_start
-                                + <$t>::from(self.v_start * t + self.j_m * t * t * t / 6.0),
+                            j,
+                            a,
+                            v: self.v_start + dv,
+                            x: self.x_start + <$t>::from(self.v_start * t) + dx,
                         }
                     } else if t <= self.t2 {
+                        let (_, _, dv1, dx1) = self.ramp(self.t1);
+                        let v1 = self.v_start + dv1;
                         let dt1 = t - self.t1;
                         $target {
                             j: Default::default(),
                             a: self.a_m,
-                            v: self.v_start
-                                + <$dt>::from(self.a_m * self.t1 / 2.0 + self.a_m * dt1),
+                            v: v1 + <$dt>::from(self.a_m * dt1),
                             x: self.x_start
-                                + <$t>::from(
-                                    self.v_start * self.t1
-                                        + self.j_m * self.t1 * self.t1 * self.t1 / 6.0
-                                        + (self.v_start + <$dt>::from(self.a_m * self.t1) / 2.0)
-                                            * dt1
-                                        + self.a_m * dt1 * dt1 / 2.0,
-                                ),
+                                + <$t>::from(self.v_start * self.t1)
+                                + dx1
+                                + <$t>::from(v1 * dt1 + self.a_m * dt1 * dt1 / 2.0),
                         }
                     } else if t <= self.t3 {
                         let dt3 = self.t3 - t;
+                        let (j, a, dv, dx) = self.ramp(dt3);
                         $target {
-                            j: -self.j_m,
-                            a: <$ddt>::from(self.j_m * dt3),
-                            v: self.v_end - <$dt>::from(self.j_m * dt3 * dt3 / 2.0),
-                            x: self.x_end - <$t>::from(self.v_end * dt3)
-                                + <$t>::from(self.j_m * dt3 * dt3 * dt3 / 6.0),
+                            j: -j,
+                            a,
+                            v: self.v_end - dv,
+                            x: self.x_end - <$t>::from(self.v_end * dt3) + dx,
                         }
                     } else {
                         $target {
@@ -337,6 +501,12 @@ macro_rules! impl_calculator_generator {
             }
 
             impl OverallCalculator {
+                /// Returns the maximum velocity, which is never lower than both ends.
+                #[allow(unused)]
+                pub fn peak_velocity(&self) -> $dt {
+                    self.const_calculator.v
+                }
+
                 pub fn calculate(&self, t: Time) -> $target {
                     let target = if t.get::<second>() < 0.0 {
                         $target {
@@ -382,6 +552,8 @@ impl_calculator_generator!(
     Acceleration,
     Jerk,
     meter,
+    meter_per_second,
+    meter_per_second_squared,
     LengthTarget
 );
 
@@ -396,9 +568,10 @@ impl_calculator_generator!(
     AngularAcceleration,
     AngularJerk,
     radian,
+    radian_per_second,
+    radian_per_second_squared,
     AngleTarget
 );
-
 pub struct StraightGenerator {
     function_generator: LengthStraightCalculatorGenerator,
     period: Time,
@@ -411,6 +584,24 @@ impl StraightGenerator {
             period,
         }
     }
+
+    /// Creates a generator whose deceleration is limited separately from its acceleration.
+    pub fn with_profile(
+        v_max: Velocity,
+        a_accel: Acceleration,
+        j_accel: Jerk,
+        a_decel: Acceleration,
+        j_decel: Jerk,
+        shape: ProfileShape,
+        period: Time,
+    ) -> Self {
+        Self {
+            function_generator: LengthStraightCalculatorGenerator::with_profile(
+                v_max, a_accel, j_accel, a_decel, j_decel, shape,
+            ),
+            period,
+        }
+    }
 }
 
 impl StraightGenerator {
@@ -446,6 +637,28 @@ impl StraightGenerator {
         )
     }
 
+    /// Returns the summary of the trajectory which
+    /// [`generate_with_terminal_velocity`](Self::generate_with_terminal_velocity) generates.
+    pub fn summarize(
+        &self,
+        distance: Length,
+        v_start: Velocity,
+        v_end: Velocity,
+    ) -> TrajectorySummary {
+        let (calculator, t_end, terminal_velocity) = self
+            .function_generator
+            .generate_with_terminal_velocity(Default::default(), distance, v_start, v_end);
+        TrajectorySummary {
+            duration: t_end,
+            end_velocity: terminal_velocity,
+            peak_velocity: calculator.peak_velocity(),
+            end_pose: Pose {
+                x: distance,
+                ..Default::default()
+            },
+        }
+    }
+
     pub fn generate_constant(distance: Length, v: Velocity, period: Time) -> StraightTrajectory {
         let (trajectory_fn, t_end) =
             LengthStraightCalculatorGenerator::generate_constant(Default::default(), distance, v);
@@ -503,24 +716,32 @@ impl Iterator for StraightTrajectory {
         }
         let t = self.t;
         self.t += self.period;
+        Some(self.evaluate(t))
+    }
+}
+
+impl TimedTrajectory for StraightTrajectory {
+    fn duration(&self) -> Time {
+        self.t_end
+    }
+
+    fn evaluate(&self, t: Time) -> Target {
         let target = match &self.trajectory_calculator {
             StraightTrajectoryCalculator::Accel(calculator) => calculator.calculate(t),
             StraightTrajectoryCalculator::Constant(calculator) => calculator.calculate(t),
         };
-        Some(Target {
+        Target {
             x: target,
             ..Default::default()
-        })
+        }
     }
 
-    #[cfg(nightly)]
-    fn advance_by(&mut self, n: usize) -> Result<(), usize> {
-        self.t += self.period * n as f32;
-        if self.t < self.t_end {
-            Ok(())
-        } else {
-            Err(n - ((self.t - self.t_end) / self.period).get::<uom::si::ratio::ratio>() as usize)
-        }
+    fn seek(&mut self, t: Time) {
+        self.t = t;
+    }
+
+    fn next_time(&self) -> Time {
+        self.t
     }
 }
 
@@ -534,7 +755,7 @@ mod tests {
         time::second, velocity::meter_per_second,
     };
 
-    const EPSILON: f32 = 1e-2; //low accuracy...
+    const EPSILON: f32 = 1e-4;
 
     use proptest::prelude::*;
 
@@ -649,20 +870,38 @@ mod tests {
             distance in 0.0f32..400.0f32,
             (v_max, v_start, v_end) in (0.5f32..10.0f32)
                 .prop_flat_map(|v_max| (Just(v_max), 0.0..v_max, 0.0..v_max)),
-            a_max in 0.5f32..10.0f32,
-            j_max in 0.5f32..10.0f32,
+            a_accel in 0.5f32..10.0f32,
+            j_accel in 0.5f32..10.0f32,
+            a_decel in 0.5f32..10.0f32,
+            j_decel in 0.5f32..10.0f32,
+            shape in prop_oneof![
+                Just(ProfileShape::Trapezoidal),
+                Just(ProfileShape::SCurve),
+                Just(ProfileShape::SinusoidalJerk),
+            ],
             period in 0.001f32..0.01f32,
         ) {
             let period = Time::new::<second>(period);
             let v_max = Velocity::new::<meter_per_second>(v_max);
-            let a_max = Acceleration::new::<meter_per_second_squared>(a_max);
-            let j_max = Jerk::new::<meter_per_second_cubed>(j_max);
-            let generator = StraightGenerator::new(v_max, a_max, j_max, period);
+            let a_max = Acceleration::new::<meter_per_second_squared>(a_accel.max(a_decel));
+            let j_max = Jerk::new::<meter_per_second_cubed>(j_accel.max(j_decel));
+            let generator = StraightGenerator::with_profile(
+                v_max,
+                Acceleration::new::<meter_per_second_squared>(a_accel),
+                Jerk::new::<meter_per_second_cubed>(j_accel),
+                Acceleration::new::<meter_per_second_squared>(a_decel),
+                Jerk::new::<meter_per_second_cubed>(j_decel),
+                shape,
+                period,
+            );
             let mut trajectory = generator.generate(
                 Length::new::<meter>(distance),
                 Velocity::new::<meter_per_second>(v_start),
                 Velocity::new::<meter_per_second>(v_end),
             );
+            // times of long trajectories are rounded in f32.
+            let dt = trajectory.duration() * (4.0 * f32::EPSILON);
+            let end = trajectory.end_pose().x;
 
             let mut before = trajectory.next().unwrap();
             for target in trajectory {
@@ -673,12 +912,95 @@ mod tests {
                 let vd = ((target.x.v-before.x.v) * cos + (target.y.v-before.y.v) * sin).abs();
                 let ad = ((target.x.a-before.x.a) * cos + (target.y.a-before.y.a) * sin).abs();
 
-                prop_assert!(xd <= v_max * period + Length::new::<meter>(EPSILON));
-                prop_assert!(vd <= a_max * period + Velocity::new::<meter_per_second>(EPSILON));
-                prop_assert!(ad <= j_max * period + Acceleration::new::<meter_per_second_squared>(EPSILON));
+                prop_assert!(xd <= v_max * (period + dt) + Length::new::<meter>(EPSILON));
+                prop_assert!(vd <= a_max * (period + dt) + Velocity::new::<meter_per_second>(EPSILON));
+                if shape != ProfileShape::Trapezoidal {
+                    prop_assert!(ad <= j_max * (period + dt) + Acceleration::new::<meter_per_second_squared>(EPSILON));
+                }
 
                 before = target;
             }
+            prop_assert!(
+                (end.get::<meter>() - distance).abs() <= EPSILON + distance * f32::EPSILON * 4.0,
+                "{:?}", end
+            );
+        }
+    }
+
+    #[test]
+    fn test_summarize_straight() {
+        use approx::assert_relative_eq;
+
+        let period = Time::new::<second>(0.001);
+        let generator = StraightGenerator::new(
+            Velocity::new::<meter_per_second>(2.0),
+            Acceleration::new::<meter_per_second_squared>(5.0),
+            Jerk::new::<meter_per_second_cubed>(100.0),
+            period,
+        );
+        let distance = Length::new::<meter>(0.18);
+        let v_start = Velocity::new::<meter_per_second>(0.2);
+        let v_end = Velocity::new::<meter_per_second>(0.4);
+        let summary = generator.summarize(distance, v_start, v_end);
+        let (trajectory, terminal_velocity) =
+            generator.generate_with_terminal_velocity(distance, v_start, v_end);
+
+        assert_eq!(summary.duration, trajectory.duration());
+        assert_eq!(summary.end_velocity, terminal_velocity);
+        assert_relative_eq!(
+            summary.end_pose.x.get::<meter>(),
+            trajectory.end_pose().x.get::<meter>(),
+            epsilon = 1e-4
+        );
+        let peak = trajectory
+            .map(|target| target.x.v.get::<meter_per_second>())
+            .fold(0.0, f32::max);
+        assert_relative_eq!(
+            summary.peak_velocity.get::<meter_per_second>(),
+            peak,
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn test_profile_shapes() {
+        use approx::assert_relative_eq;
+
+        let period = Time::new::<second>(0.0005);
+        let v_max = Velocity::new::<meter_per_second>(2.0);
+        let a_accel = Acceleration::new::<meter_per_second_squared>(5.0);
+        let a_decel = Acceleration::new::<meter_per_second_squared>(10.0);
+        let j_max = Jerk::new::<meter_per_second_cubed>(100.0);
+        let distance = Length::new::<meter>(1.0);
+
+        let peaks = |shape| {
+            let generator = StraightGenerator::with_profile(
+                v_max, a_accel, j_max, a_decel, j_max, shape, period,
+            );
+            let trajectory = generator.generate(distance, Default::default(), Default::default());
+            assert_relative_eq!(
+                trajectory.end_pose().x.get::<meter>(),
+                distance.get::<meter>(),
+                epsilon = 1e-5
+            );
+            trajectory.fold((0.0f32, 0.0f32, 0.0f32), |(a_max, a_min, j), target| {
+                (
+                    a_max.max(target.x.a.value),
+                    a_min.min(target.x.a.value),
+                    j.max(target.x.j.value.abs()),
+                )
+            })
+        };
+
+        let (a_max, a_min, j) = peaks(ProfileShape::Trapezoidal);
+        assert_relative_eq!(a_max, 5.0, epsilon = 1e-4);
+        assert_relative_eq!(a_min, -10.0, epsilon = 1e-4);
+        assert_eq!(j, 0.0);
+        for shape in [ProfileShape::SCurve, ProfileShape::SinusoidalJerk] {
+            let (a_max, a_min, j) = peaks(shape);
+            assert_relative_eq!(a_max, 5.0, epsilon = 1e-4);
+            assert_relative_eq!(a_min, -10.0, epsilon = 1e-4);
+            assert_relative_eq!(j, 100.0, epsilon = 1e-1);
         }
     }
 }
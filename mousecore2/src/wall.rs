@@ -3,6 +3,7 @@ use core::{fmt, marker::PhantomData};
 use heapless::Vec;
 #[allow(unused_imports)]
 use micromath::F32Ext;
+use serde::{Deserialize, Serialize};
 use uom::si::{
     angle::{degree, revolution},
     f32::{Angle, Length},
@@ -21,7 +22,7 @@ pub struct WallInfo<const W: u8> {
     pub not_existing_distance: Length,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
 pub struct Pose {
     pub x: Length,
     pub y: Length,
@@ -75,10 +76,15 @@ impl Pose {
 
 const EXISTENCE_ARRAY_LEN: usize = WIDTH * WIDTH * 2;
 
+/// The number of recently checked walls which [`WallDetector`] remembers.
+pub const RECENT_CHECKED_MAX: usize = 32;
+
 #[derive(Debug)]
 pub struct WallDetector<const W: u8> {
     converter: PoseConverter<W>,
     wall_existence_array: [Probability; EXISTENCE_ARRAY_LEN],
+    // recently checked walls from the oldest one
+    recent_checked: Vec<Coordinate<W>, RECENT_CHECKED_MAX>,
 }
 
 #[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
@@ -110,11 +116,11 @@ impl Probability {
     }
 
     fn is_one(&self) -> bool {
-        (self.0 - 1.0).abs() < core::f32::EPSILON
+        (self.0 - 1.0).abs() < f32::EPSILON
     }
 
     fn is_zero(&self) -> bool {
-        self.0.abs() < core::f32::EPSILON
+        self.0.abs() < f32::EPSILON
     }
 }
 
@@ -126,9 +132,15 @@ impl<const W: u8> Default for WallDetector<W> {
 
 impl<const W: u8> WallDetector<W> {
     pub fn new() -> Self {
+        Self::with_converter(PoseConverter::default())
+    }
+
+    /// Returns a detector which finds walls seen from poses with `converter`.
+    pub fn with_converter(converter: PoseConverter<W>) -> Self {
         Self {
-            converter: PoseConverter::default(),
+            converter,
             wall_existence_array: [Probability::mid(); EXISTENCE_ARRAY_LEN],
+            recent_checked: Vec::new(),
         }
     }
 
@@ -158,6 +170,43 @@ impl<const W: u8> WallDetector<W> {
         &mut self.wall_existence_array[coord.as_index()]
     }
 
+    /// Returns how confident the detector is about the state of the given wall in [0, 1].
+    pub fn confidence(&self, coord: &Coordinate<W>) -> f32 {
+        (self.wall_existence(coord).0 - 0.5).abs() * 2.0
+    }
+
+    /// Returns the order in which the given wall was checked.
+    ///
+    /// Larger values mean more recent detections and 0 means the wall is not among
+    /// the latest [`RECENT_CHECKED_MAX`] checked walls.
+    pub fn checked_order(&self, coord: &Coordinate<W>) -> u16 {
+        self.recent_checked
+            .iter()
+            .position(|recent| recent == coord)
+            .map_or(0, |index| index as u16 + 1)
+    }
+
+    /// Returns whether the wall seen from `pose` exists.
+    ///
+    /// Returns `None` if no wall is visible from `pose` or the wall has not been decided yet.
+    pub fn wall_exists(&self, pose: &Pose) -> Option<bool> {
+        let wall_info = self.converter.convert(pose)?;
+        let existence = *self.wall_existence(&wall_info.coord);
+        if existence < WALL_EXISTENCE_TH {
+            Some(false)
+        } else if existence > WALL_EXISTENCE_TH.reverse() {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Forgets the detection result of the given wall so that it is checked again.
+    pub fn reset(&mut self, coord: &Coordinate<W>) {
+        *self.wall_existence_mut(coord) = Probability::mid();
+        self.recent_checked.retain(|recent| recent != coord);
+    }
+
     pub fn detect_and_update(
         &mut self,
         &dist_mean: &Length,
@@ -212,6 +261,14 @@ impl<const W: u8> WallDetector<W> {
         } else {
             WallState::Unchecked
         };
+        if state != WallState::Unchecked && !self.recent_checked.contains(&wall_info.coord) {
+            if self.recent_checked.is_full() {
+                self.recent_checked.remove(0);
+            }
+            self.recent_checked
+                .push(wall_info.coord)
+                .expect("Should never fail: the oldest wall has been removed if full.");
+        }
 
         Some((wall_info.coord, state))
     }
@@ -237,17 +294,17 @@ const DEFAULT_SQUARE_WIDTH: Length = Length {
     units: PhantomData,
     value: 0.09,
 };
-const DEFAULT_WALL_WIDTH: Length = Length {
+pub(crate) const DEFAULT_WALL_WIDTH: Length = Length {
     dimension: PhantomData,
     units: PhantomData,
     value: 0.006,
 };
-const DEFAULT_IGNORE_RADIUS: Length = Length {
+pub(crate) const DEFAULT_IGNORE_RADIUS: Length = Length {
     dimension: PhantomData,
     units: PhantomData,
     value: 0.01,
 };
-const DEFAULT_IGNORE_LENGTH: Length = Length {
+pub(crate) const DEFAULT_IGNORE_LENGTH: Length = Length {
     value: 0.008,
     dimension: PhantomData,
     units: PhantomData,
@@ -265,7 +322,11 @@ impl<const W: u8> Default for PoseConverter<W> {
 }
 
 impl<const W: u8> PoseConverter<W> {
-    fn new(
+    /// Returns a converter for walls of `wall_width` on the grid of `square_width`.
+    ///
+    /// Poses whose line of sight passes within `ignore_radius_from_pillar` of a pillar or
+    /// which are within `ignore_length_from_wall` of the wall are not converted.
+    pub fn new(
         square_width: Length,
         wall_width: Length,
         ignore_radius_from_pillar: Length,
@@ -326,7 +387,7 @@ impl<const W: u8> PoseConverter<W> {
         let mind = pillars
             .iter()
             .map(|pillar| (sin_th * (pose.x - pillar.0) - cos_th * (pose.y - pillar.1)).abs())
-            .fold(Length::new::<meter>(core::f32::INFINITY), |m, v| m.min(v));
+            .fold(Length::new::<meter>(f32::INFINITY), |m, v| m.min(v));
 
         mind < self.ignore_radius_from_pillar
     }
@@ -414,7 +475,7 @@ impl<const W: u8> PoseConverter<W> {
                 };
                 //assign infinity to invalid values
                 let dist = if dist.get::<meter>() < 0.0 || dist.get::<meter>().is_nan() {
-                    Length::new::<meter>(core::f32::INFINITY)
+                    Length::new::<meter>(f32::INFINITY)
                 } else {
                     dist
                 };
@@ -770,6 +831,38 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_recent_checked() {
+        let mut detector = WallDetector::<32>::new();
+        // walls on the right of cells in the bottom rows
+        let pose = |i: usize| Pose {
+            x: Length::new::<meter>(0.045 + 0.09 * (i % 32) as f32),
+            y: Length::new::<meter>(0.045 + 0.09 * (i / 32) as f32),
+            theta: Default::default(),
+        };
+        let coords = (0..=RECENT_CHECKED_MAX)
+            .map(|i| {
+                let (coord, state) = detector
+                    .detect_and_update(
+                        &Length::new::<meter>(0.042),
+                        &Length::new::<meter>(0.002),
+                        &pose(i),
+                    )
+                    .unwrap();
+                assert_eq!(state, WallState::Checked { exists: true });
+                coord
+            })
+            .collect::<std::vec::Vec<_>>();
+
+        // the oldest wall has been forgotten.
+        assert_eq!(detector.checked_order(&coords[0]), 0);
+        assert_eq!(detector.checked_order(&coords[1]), 1);
+        let latest = &coords[RECENT_CHECKED_MAX];
+        assert_eq!(detector.checked_order(latest), RECENT_CHECKED_MAX as u16);
+        detector.reset(latest);
+        assert_eq!(detector.checked_order(latest), 0);
+    }
+
     define_convert_ok_test! (
     convert_ok_test1: (4, (
         (0.045, 0.045, 0.0),
@@ -0,0 +1,75 @@
+use approx::assert_relative_eq;
+use mousecore2::{
+    control::identify::{Excitation, Identifier, TuningRule},
+    estimate::{SensorValue, State},
+};
+use mousesim2::Simulator;
+use uom::si::f32::{ElectricPotential, Frequency, Length, Time};
+use uom::si::{electric_potential::volt, frequency::hertz, length::millimeter, time::second};
+
+#[test]
+fn test_identify_with_simulator() {
+    let period = Time::new::<second>(0.001);
+    let trans_k = 1.865;
+    let trans_t1 = Time::new::<second>(0.4443);
+    let rot_k = 82.39;
+    let rot_t1 = Time::new::<second>(0.2855);
+
+    let mut simulator = Simulator::<16>::builder()
+        .period(period)
+        .trans_k(trans_k)
+        .trans_t1(trans_t1)
+        .rot_k(rot_k)
+        .rot_t1(rot_t1)
+        .walls(include_str!("../mazes/maze16_1.dat"))
+        .wheel_interval(Length::new::<millimeter>(33.5))
+        .current(State::default())
+        .last(State::default())
+        .max_voltage(ElectricPotential::new::<volt>(3.7))
+        .build();
+    let mut identifier = Identifier::builder().period(period).build();
+
+    let step = Excitation::Step {
+        voltage: ElectricPotential::new::<volt>(1.0),
+    };
+    let chirp = Excitation::Chirp {
+        amplitude: ElectricPotential::new::<volt>(0.5),
+        start: Frequency::new::<hertz>(0.2),
+        end: Frequency::new::<hertz>(5.0),
+        duration: Time::new::<second>(2.0),
+    };
+    let mut run = |voltage_at: &dyn Fn(Time) -> _| {
+        for i in 0..2000 {
+            let voltage = voltage_at(period * i as f32);
+            simulator.apply(&voltage);
+            simulator.step();
+            let distance = simulator.distance();
+            identifier.push(
+                &voltage,
+                &SensorValue {
+                    left_distance: distance.left,
+                    right_distance: distance.right,
+                    translational_acceleration: simulator.translational_acceleration(),
+                    angular_velocity: simulator.angular_velocity(),
+                },
+            );
+        }
+    };
+    run(&|t| step.translational(t));
+    run(&|t| chirp.rotational(t));
+
+    let trans = identifier.translational().unwrap();
+    let rot = identifier.rotational().unwrap();
+    assert_relative_eq!(trans.k, trans_k, max_relative = 0.01);
+    assert_relative_eq!(trans.t1.value, trans_t1.value, max_relative = 0.01);
+    assert_relative_eq!(rot.k, rot_k, max_relative = 0.01);
+    assert_relative_eq!(rot.t1.value, rot_t1.value, max_relative = 0.01);
+
+    let params = rot
+        .tune(&TuningRule::Imc {
+            time_constant: Time::new::<second>(0.02),
+        })
+        .unwrap();
+    assert_relative_eq!(params.model_k, rot.k);
+    assert!(params.kp > 0.0 && params.ki > 0.0);
+}
@@ -1,86 +1,113 @@
 use mousecore2::{
     control::{
-        ControlParameters, Controller, NavigationController, SupervisoryController, Target, Tracker,
+        identify::FirstOrderModel,
+        mpc::MpcTracker,
+        pursuit::{PathTracker, PathTrackingLaw},
+        ControlParameters, ControlTarget, Controller, NavigatedTracker, NavigationController,
+        SupervisoryController, Target, TargetTracker, Tracker, TrackingInput,
     },
     estimate::{AngleState, Estimator, LengthState, SensorValue, State},
     solve::{
         run::{shortest_path, EdgeKind, Node, Posture, TrajectoryKind},
         search::WallState,
+        velocity::{VelocityPlan, VelocityPlanner},
     },
     trajectory::{
-        slalom::{SlalomConfig, SlalomGenerator},
+        sequence::TrajectorySequence,
+        slalom::{SlalomConfig, SlalomGenerator, SlalomKind},
         straight::StraightGenerator,
-        ShiftTrajectory,
     },
     wall::{Pose, Walls},
 };
 use mousesim2::Simulator;
-use uom::si::f32::{Acceleration, Angle, ElectricPotential, Jerk, Length, Time, Velocity};
+use uom::si::f32::{
+    Acceleration, Angle, ElectricPotential, Frequency, Jerk, Length, Time, Velocity,
+};
 use uom::si::{
     acceleration::meter_per_second_squared, angle::degree, electric_potential::volt,
-    jerk::meter_per_second_cubed, length::millimeter, time::second, velocity::meter_per_second,
+    frequency::hertz, jerk::meter_per_second_cubed, length::meter, length::millimeter,
+    time::second, velocity::meter_per_second,
 };
 
 use Posture::*;
 
+const GOALS: [(u8, u8, Posture); 4] = [
+    (14, 14, North),
+    (14, 14, East),
+    (14, 14, West),
+    (14, 14, South),
+];
+
 #[test]
 fn test_run1() {
-    test_run::<16>(
-        include_str!("../mazes/maze16_1.dat"),
-        &[
-            (14, 14, North),
-            (14, 14, East),
-            (14, 14, West),
-            (14, 14, South),
-        ],
-    );
+    let input = include_str!("../mazes/maze16_1.dat");
+    let walls = input.parse::<Walls<16>>().unwrap();
+    test_run::<16>(input, &GOALS, &mut nonlinear_tracker(&walls));
 }
 
 #[test]
 fn test_run2() {
-    test_run::<16>(
-        include_str!("../mazes/maze16_3.dat"),
-        &[
-            (14, 14, North),
-            (14, 14, East),
-            (14, 14, West),
-            (14, 14, South),
-        ],
+    let input = include_str!("../mazes/maze16_3.dat");
+    let walls = input.parse::<Walls<16>>().unwrap();
+    test_run::<16>(input, &GOALS, &mut nonlinear_tracker(&walls));
+}
+
+#[test]
+fn test_run_mpc() {
+    let input = include_str!("../mazes/maze16_1.dat");
+    let walls = input.parse::<Walls<16>>().unwrap();
+    let error = test_run::<16>(input, &GOALS, &mut nonlinear_tracker(&walls));
+    let mpc_error = test_run::<16>(input, &GOALS, &mut mpc_tracker());
+    assert!(
+        mpc_error < Length::new::<millimeter>(5.0) && mpc_error <= error,
+        "nonlinear: {:?}, mpc: {:?}",
+        error,
+        mpc_error
     );
 }
 
-fn test_run<const W: u8>(input: &'static str, goals: &[(u8, u8, Posture)]) {
-    let goals = goals
-        .into_iter()
-        .map(|&(x, y, dir)| Node::new(x, y, dir).unwrap())
-        .collect::<Vec<_>>();
-    // common settings
-    let period = Time::new::<second>(0.001);
-    let trans_k = 1.865;
-    let trans_t1 = Time::new::<second>(0.4443);
-    let rot_k = 82.39;
-    let rot_t1 = Time::new::<second>(0.2855);
-    let state = State {
-        x: LengthState {
-            x: Length::new::<millimeter>(45.0),
-            ..Default::default()
-        },
-        y: LengthState {
-            x: Length::new::<millimeter>(45.0),
-            ..Default::default()
+#[test]
+fn test_run_path() {
+    let input = include_str!("../mazes/maze16_1.dat");
+    for law in [
+        PathTrackingLaw::PurePursuit {
+            lookahead: Length::new::<millimeter>(20.0),
         },
-        theta: AngleState {
-            x: Angle::new::<degree>(90.0),
-            ..Default::default()
+        PathTrackingLaw::Stanley {
+            gain: Frequency::new::<hertz>(20.0),
+            softening: Velocity::new::<meter_per_second>(0.1),
+            heading_gain: Frequency::new::<hertz>(20.0),
         },
-    };
+    ] {
+        let error = test_run_with_path::<16>(input, &GOALS, law);
+        assert!(
+            error < Length::new::<millimeter>(5.0),
+            "{:?}: {:?}",
+            law,
+            error
+        );
+    }
+}
 
-    let square_width = Length::new::<millimeter>(90.0);
+// common settings
+fn period() -> Time {
+    Time::new::<second>(0.001)
+}
 
-    let mut state = state;
-    let walls = input.parse::<Walls<W>>().unwrap();
-    let mut tracker = Tracker::builder()
-        .period(period)
+fn square_width() -> Length {
+    Length::new::<millimeter>(90.0)
+}
+
+const TRANS_K: f32 = 1.865;
+const TRANS_T1: f32 = 0.4443;
+const ROT_K: f32 = 82.39;
+const ROT_T1: f32 = 0.2855;
+
+fn nonlinear_tracker<const W: u8>(
+    walls: &Walls<W>,
+) -> NavigatedTracker<impl FnMut(&State, &Target) -> TrackingInput + '_> {
+    let tracker = Tracker::builder()
+        .period(period())
         .zeta(1.0)
         .b(1.0)
         .xi_threshold(Velocity::new::<meter_per_second>(0.2))
@@ -90,28 +117,104 @@ fn test_run<const W: u8>(input: &'static str, goals: &[(u8, u8, Posture)]) {
         .dgain(4.0)
         .build();
     let supervisor = SupervisoryController::builder()
-        .square_width(square_width)
+        .square_width(square_width())
         .margin(100.0)
         .avoidance_distance(Length::new::<millimeter>(20.0))
         .build();
-    let mut controller = Controller::builder()
-        .trans_params(ControlParameters {
-            kp: 4.8497,
-            ki: 29.5783,
-            kd: 0.0,
-            model_k: trans_k,
-            model_t1: trans_t1.value,
+    NavigatedTracker::new(tracker, move |state: &State, target: &Target| {
+        let input = navigation.navigate(state, target);
+        supervisor
+            .supervise_with_walls(&input, state, walls)
+            .unwrap()
+    })
+}
+
+fn mpc_tracker() -> MpcTracker<10> {
+    MpcTracker::builder()
+        .period(period())
+        .trans_model(FirstOrderModel {
+            k: TRANS_K,
+            t1: Time::new::<second>(TRANS_T1),
         })
-        .rot_params(ControlParameters {
-            kp: 0.21134,
-            ki: 2.9317,
-            kd: 0.0,
-            model_k: rot_k,
-            model_t1: rot_t1.value,
+        .rot_model(FirstOrderModel {
+            k: ROT_K,
+            t1: Time::new::<second>(ROT_T1),
         })
-        .period(period)
-        .build();
-    let mut estimator = Estimator::builder().period(period).build();
+        .max_voltage(ElectricPotential::new::<volt>(3.7))
+        .build()
+}
+
+// Return the maximum distance between the estimated and target positions.
+fn test_run<const W: u8>(
+    input: &'static str,
+    goals: &[(u8, u8, Posture)],
+    tracker: &mut impl TargetTracker,
+) -> Length {
+    let targets = segments::<W>(input, goals).concat();
+    let mut robot = Robot::<W>::new(input);
+    let mut max_error = Length::default();
+    for i in 0..targets.len() {
+        robot.step(|state| {
+            let error = distance(state, &targets[i]);
+            if error > max_error {
+                max_error = error;
+            }
+            tracker.track_targets(state, &targets[i..])
+        });
+    }
+    max_error
+}
+
+// Return the maximum distance between the estimated positions and the targets
+// onto which the robot is projected.
+fn test_run_with_path<const W: u8>(
+    input: &'static str,
+    goals: &[(u8, u8, Posture)],
+    law: PathTrackingLaw,
+) -> Length {
+    let segments = segments::<W>(input, goals);
+    let mut robot = Robot::<W>::new(input);
+    let mut tracker = PathTracker::builder().period(period()).law(law).build();
+    let mut max_error = Length::default();
+    for segment in &segments {
+        tracker.reset();
+        // the path tracker is not bound to the timing of the targets, and slowly approaches
+        // the end of a stop.
+        for _ in 0..3 * segment.len() {
+            robot.step(|state| {
+                let output = tracker.track(state, segment);
+                let error = distance(state, &segment[tracker.progress()]);
+                if error > max_error {
+                    max_error = error;
+                }
+                output
+            });
+            if tracker.progress() + 1 == segment.len() {
+                break;
+            }
+        }
+        assert_eq!(tracker.progress() + 1, segment.len());
+    }
+    max_error
+}
+
+fn distance(state: &State, target: &Target) -> Length {
+    Length::new::<meter>(
+        (target.x.x - state.x.x)
+            .value
+            .hypot((target.y.x - state.y.x).value),
+    )
+}
+
+// Return the targets of each segment of the shortest run to `goals`.
+fn segments<const W: u8>(input: &'static str, goals: &[(u8, u8, Posture)]) -> Vec<Vec<Target>> {
+    let goals = goals
+        .iter()
+        .map(|&(x, y, dir)| Node::new(x, y, dir).unwrap())
+        .collect::<Vec<_>>();
+    let period = period();
+    let square_width = square_width();
+    let walls = input.parse::<Walls<W>>().unwrap();
 
     let v_max = Velocity::new::<meter_per_second>(1.0);
     let a_max = Acceleration::new::<meter_per_second_squared>(10.0);
@@ -120,19 +223,7 @@ fn test_run<const W: u8>(input: &'static str, goals: &[(u8, u8, Posture)]) {
     let slalom_config = SlalomConfig::new(square_width, Default::default());
     let slalom = SlalomGenerator::new(period, v_max, a_max, j_max);
     let straight = StraightGenerator::new(v_max, a_max, j_max, period);
-
-    let mut simulator = Simulator::<W>::builder()
-        .period(period)
-        .trans_k(trans_k)
-        .trans_t1(trans_t1)
-        .rot_k(rot_k)
-        .rot_t1(rot_t1)
-        .walls(input)
-        .wheel_interval(Length::new::<millimeter>(33.5))
-        .current(state.clone())
-        .last(state.clone())
-        .max_voltage(ElectricPotential::new::<volt>(3.7))
-        .build();
+    let planner = VelocityPlanner::new(v_max, a_max, j_max, square_width);
 
     let (path, _) = shortest_path(
         Node::new(0, 0, North).unwrap(),
@@ -151,98 +242,154 @@ fn test_run<const W: u8>(input: &'static str, goals: &[(u8, u8, Posture)]) {
             EdgeKind::Slalom135 => 20,
             EdgeKind::Slalom180 => 25,
             EdgeKind::SlalomDiagonal90 => 15,
+            EdgeKind::SlalomDiagonal90Large => 20,
+            EdgeKind::SlalomKojima => 22,
         },
     )
     .unwrap();
 
-    let mut trajectories = std::collections::VecDeque::new();
-    let mut velocity = Default::default();
+    let kinds = path
+        .windows(2)
+        .map(|pair| {
+            pair[0]
+                .trajectory_kind(&pair[1])
+                .unwrap_or_else(|| unreachable!("{:?}", pair))
+        })
+        .collect::<Vec<_>>();
     let run_slalom_velocity = Velocity::new::<meter_per_second>(0.5);
-    for i in 0..path.len() - 1 {
+    let plans = planner.plan(
+        &kinds,
+        &slalom_config,
+        Default::default(),
+        Default::default(),
+        |_| run_slalom_velocity,
+    );
+
+    let mut trajectories = TrajectorySequence::<128>::new(period);
+    let mut segments = Vec::new();
+    for ((node, kind), plan) in path.iter().zip(kinds).zip(plans) {
         use TrajectoryKind::*;
 
-        let pose = Pose::from_node(path[i], square_width);
-        let kind = path[i]
-            .trajectory_kind(&path[i + 1])
-            .unwrap_or_else(|| unreachable!("{:?}", (path[i], path[i + 1])));
-        let (start, middle, end) = if i <= 1 {
-            (velocity, run_slalom_velocity, run_slalom_velocity)
-        } else if i + 2 == path.len() {
-            (velocity, velocity * 0.666_666_7, Default::default())
-        } else if i + 3 == path.len() {
-            (velocity, velocity * 0.5, velocity * 0.5)
-        } else {
-            (
-                run_slalom_velocity,
-                run_slalom_velocity,
-                run_slalom_velocity,
-            )
-        };
-        macro_rules! gen {
-            ($input: expr) => {{
-                let (trajectory, terminal_velocity) = $input;
-                (
-                    Box::new(ShiftTrajectory::new(pose, trajectory)),
-                    terminal_velocity,
-                )
-            }};
-        }
-        let (trajectory, terminal_velocity): (Box<dyn Iterator<Item = Target>>, _) = match kind {
+        let pose = Pose::from_node(*node, square_width);
+        let VelocityPlan { start, middle, end } = plan;
+        let pushed = match kind {
             Straight(x) => {
-                gen!(straight.generate_with_terminal_velocity(x as f32 * square_width, start, end))
+                let (trajectory, _) =
+                    straight.generate_with_terminal_velocity(x as f32 * square_width, start, end);
+                trajectories.push(pose, trajectory)
+            }
+            StraightDiagonal(x) => {
+                let (trajectory, _) = straight.generate_with_terminal_velocity(
+                    x as f32 * square_width / 2.0f32.sqrt(),
+                    start,
+                    end,
+                );
+                trajectories.push(pose, trajectory)
+            }
+            Slalom(SlalomKind::FastRunKojima, dir) => {
+                let (trajectory, _) = slalom.generate_s_slalom_with_terminal_velocity(
+                    slalom_config.parameters(SlalomKind::FastRunKojima, dir),
+                    start,
+                    middle,
+                    end,
+                );
+                trajectories.push(pose, trajectory)
             }
-            StraightDiagonal(x) => gen!(straight.generate_with_terminal_velocity(
-                x as f32 * square_width / 2.0f32.sqrt(),
-                start,
-                end
-            )),
             Slalom(kind, dir) => {
-                gen!(slalom.generate_slalom_with_terminal_velocity(
+                let (trajectory, _) = slalom.generate_slalom_with_terminal_velocity(
                     slalom_config.parameters(kind, dir),
                     start,
                     middle,
-                    end
-                ))
+                    end,
+                );
+                trajectories.push(pose, trajectory)
             }
         };
-        velocity = terminal_velocity;
-        trajectories.push_back(trajectory);
+        assert!(pushed.is_ok());
+        segments.push(trajectories.by_ref().collect::<Vec<_>>());
     }
+    assert!(!segments.is_empty());
+    segments
+}
 
-    let vol_th = ElectricPotential::new::<volt>(4.0);
+// The simulated robot with the estimator and the controller.
+struct Robot<const W: u8> {
+    state: State,
+    estimator: Estimator,
+    controller: Controller,
+    simulator: Simulator<W>,
+}
 
-    loop {
-        // estimate
+impl<const W: u8> Robot<W> {
+    fn new(input: &'static str) -> Self {
+        let period = period();
+        let state = State {
+            x: LengthState {
+                x: Length::new::<millimeter>(45.0),
+                ..Default::default()
+            },
+            y: LengthState {
+                x: Length::new::<millimeter>(45.0),
+                ..Default::default()
+            },
+            theta: AngleState {
+                x: Angle::new::<degree>(90.0),
+                ..Default::default()
+            },
+        };
+        let controller = Controller::builder()
+            .trans_params(ControlParameters {
+                kp: 4.8497,
+                ki: 29.5783,
+                kd: 0.0,
+                model_k: TRANS_K,
+                model_t1: TRANS_T1,
+            })
+            .rot_params(ControlParameters {
+                kp: 0.21134,
+                ki: 2.9317,
+                kd: 0.0,
+                model_k: ROT_K,
+                model_t1: ROT_T1,
+            })
+            .period(period)
+            .build();
+        let simulator = Simulator::<W>::builder()
+            .period(period)
+            .trans_k(TRANS_K)
+            .trans_t1(Time::new::<second>(TRANS_T1))
+            .rot_k(ROT_K)
+            .rot_t1(Time::new::<second>(ROT_T1))
+            .walls(input)
+            .wheel_interval(Length::new::<millimeter>(33.5))
+            .current(state.clone())
+            .last(state.clone())
+            .max_voltage(ElectricPotential::new::<volt>(3.7))
+            .build();
+        Self {
+            state,
+            estimator: Estimator::builder().period(period).build(),
+            controller,
+            simulator,
+        }
+    }
+
+    // Estimate the state, control the robot with the output of `track` and simulate a period.
+    fn step(&mut self, track: impl FnOnce(&State) -> (ControlTarget, ControlTarget)) {
         let sensor_value = {
-            let distance = simulator.distance();
+            let distance = self.simulator.distance();
             SensorValue {
                 left_distance: distance.left,
                 right_distance: distance.right,
-                translational_acceleration: simulator.translational_acceleration(),
-                angular_velocity: simulator.angular_velocity(),
+                translational_acceleration: self.simulator.translational_acceleration(),
+                angular_velocity: self.simulator.angular_velocity(),
             }
         };
-        estimator.estimate(&mut state, &sensor_value);
+        self.estimator.estimate(&mut self.state, &sensor_value);
 
-        // track
-        let target = if let Some(trajectory) = trajectories.front_mut() {
-            if let Some(target) = trajectory.next() {
-                target
-            } else {
-                trajectories.pop_front();
-                if let Some(trajectory) = trajectories.front_mut() {
-                    trajectory.next().unwrap()
-                } else {
-                    break;
-                }
-            }
-        } else {
-            unreachable!()
-        };
-        let input = navigation.navigate(&state, &target);
-        let input = supervisor.supervise(&input, &state);
-        let (control_target, control_state) = tracker.track(&state, &target, &input);
-        let vol = controller.control(&control_target, &control_state);
+        let (control_target, control_state) = track(&self.state);
+        let vol = self.controller.control(&control_target, &control_state);
+        let vol_th = ElectricPotential::new::<volt>(4.0);
         assert!(
             vol.left.abs() <= vol_th && vol.right.abs() <= vol_th,
             "left: {:?}, right: {:?}, th: {:?}",
@@ -250,8 +397,7 @@ fn test_run<const W: u8>(input: &'static str, goals: &[(u8, u8, Posture)]) {
             vol.right,
             vol_th
         );
-        simulator.apply(&vol);
-
-        simulator.step();
+        self.simulator.apply(&vol);
+        self.simulator.step();
     }
 }
@@ -2,7 +2,8 @@ use mousecore2::{
     control::{ControlParameters, Controller, NavigationController, Target, Tracker},
     estimate::{AngleState, Estimator, LengthState, SensorValue, State},
     solve::search::{
-        Commander, Coordinate, Posture, SearchState, Searcher, TrajectoryKind, WallState,
+        Commander, Coordinate, Posture, RecoveryAction, SearchRecovery, SearchState, Searcher,
+        TrajectoryKind, WallState,
     },
     trajectory::{
         slalom::{SlalomConfig, SlalomDirection, SlalomGenerator, SlalomKind},
@@ -89,7 +90,7 @@ fn test_search_offset2() {
 
 fn test_search<const W: u8>(input: &'static str, goals: &[(u8, u8)], front_offset: Length) {
     let goals = goals
-        .into_iter()
+        .iter()
         .map(|&(x, y)| Coordinate::new(x, y).unwrap())
         .collect::<Vec<_>>();
     // common settings
@@ -162,6 +163,7 @@ fn test_search<const W: u8>(input: &'static str, goals: &[(u8, u8)], front_offse
     let mut estimator = Estimator::builder().period(period).build();
     let mut detector = WallDetector::<W>::default();
     let searcher = Searcher::<W>::new(Coordinate::new(0, 1).unwrap(), &goals);
+    let mut recovery = SearchRecovery::default();
 
     let square_width = Length::new::<millimeter>(90.0);
 
@@ -300,12 +302,29 @@ fn test_search<const W: u8>(input: &'static str, goals: &[(u8, u8)], front_offse
                 commander.take();
             }
             Some(Ok(None)) => (),
-            Some(Err(err)) => unreachable!("{:?}", err),
+            Some(Err(err)) => match recovery.recover(&err, &detector) {
+                RecoveryAction::Retry => {
+                    commander.take();
+                }
+                action => unreachable!("{:?}", action),
+            },
             None if next.is_none() => {
-                match searcher.search(&robot.coordinate(), |coord| walls.wall_state(coord)) {
-                    Ok(Some(next)) => commander = Some(next),
+                match searcher.search_with_rank(
+                    &robot.coordinate(),
+                    |coord| walls.wall_state(coord),
+                    |coord| detector.checked_order(coord),
+                ) {
+                    Ok(Some(next)) => {
+                        recovery.reset();
+                        commander = Some(next);
+                    }
                     Ok(None) => break,
-                    Err(err) => unreachable!("{:?}", err),
+                    Err(err) => match recovery.recover(&err, &detector) {
+                        action @ RecoveryAction::Reverify(_) => {
+                            action.apply(&mut walls, &mut detector)
+                        }
+                        action => unreachable!("{:?}", action),
+                    },
                 }
             }
             _ => (),
@@ -0,0 +1,213 @@
+use core::f32::consts::FRAC_PI_4;
+
+use mousecore2::{
+    solve::search::{
+        AdachiSearcher, Coordinate, Hand, HandSearcher, Posture, SearchState, SearchStrategy,
+        Searcher, TrajectoryKind, WallState,
+    },
+    wall::Walls,
+};
+
+// name, width, goals and the maze
+#[allow(clippy::type_complexity)]
+const MAZES: [(&str, u8, [(u8, u8); 2], &str); 9] = [
+    (
+        "maze4_1",
+        4,
+        [(2, 1), (3, 0)],
+        include_str!("../mazes/maze4_1.dat"),
+    ),
+    (
+        "maze4_2",
+        4,
+        [(2, 1), (3, 0)],
+        include_str!("../mazes/maze4_2.dat"),
+    ),
+    (
+        "maze4_3",
+        4,
+        [(2, 1), (3, 0)],
+        include_str!("../mazes/maze4_3.dat"),
+    ),
+    (
+        "maze4_4",
+        4,
+        [(2, 1), (3, 0)],
+        include_str!("../mazes/maze4_4.dat"),
+    ),
+    (
+        "maze16_1",
+        16,
+        [(15, 14), (14, 15)],
+        include_str!("../mazes/maze16_1.dat"),
+    ),
+    (
+        "maze16_2",
+        16,
+        [(15, 14), (14, 15)],
+        include_str!("../mazes/maze16_2.dat"),
+    ),
+    (
+        "maze16_3",
+        16,
+        [(15, 14), (14, 15)],
+        include_str!("../mazes/maze16_3.dat"),
+    ),
+    (
+        "maze32_1",
+        32,
+        [(37, 28), (36, 29)],
+        include_str!("../mazes/maze32_1.dat"),
+    ),
+    (
+        "maze32_2",
+        32,
+        [(15, 14), (14, 15)],
+        include_str!("../mazes/maze32_2.dat"),
+    ),
+];
+
+// The result of a search.
+#[derive(Clone, Copy, Debug)]
+struct Outcome {
+    // the travelled distance in squares until the robot first reaches one of the goals
+    to_goal: Option<f32>,
+    // the number of coordinates the robot visits until then
+    visited: usize,
+    // true if the strategy finishes
+    finished: bool,
+}
+
+// Run a search on an ideal robot which checks every wall of the square in front of it.
+fn simulate<const W: u8>(
+    input: &str,
+    goals: &[Coordinate<W>],
+    mut strategy: impl SearchStrategy<W>,
+) -> Outcome {
+    let answer = input.parse::<Walls<W>>().unwrap();
+    let mut walls = Walls::<W>::new();
+    let mut state = SearchState::new(Coordinate::new(0, 1).unwrap(), Posture::North).unwrap();
+    let offsets = |posture| {
+        let (dx, dy): (i8, i8) = match posture {
+            Posture::North => (0, 1),
+            Posture::East => (1, 0),
+            Posture::South => (0, -1),
+            Posture::West => (-1, 0),
+        };
+        [(2 * dx, 2 * dy), (dx - dy, dy + dx), (dx + dy, dy - dx)]
+    };
+    let observe = |state: &SearchState<W>, walls: &mut Walls<W>| {
+        for (dx, dy) in offsets(state.posture()) {
+            let x = state.x() as i8 + dx;
+            let y = state.y() as i8 + dy;
+            if x < 0 || y < 0 {
+                continue;
+            }
+            if let Some(coord) = Coordinate::new(x as u8, y as u8) {
+                walls.update(&coord, &answer.wall_state(&coord));
+            }
+        }
+    };
+    observe(&state, &mut walls);
+
+    let mut outcome = Outcome {
+        to_goal: None,
+        visited: 0,
+        finished: false,
+    };
+    let mut visited = std::collections::HashSet::new();
+    visited.insert((state.x(), state.y()));
+    let mut distance = 0.0;
+    for _ in 0..20 * W as usize * W as usize {
+        let commander =
+            match strategy.next_commander(&state, |coord| walls.wall_state(coord), |_| 0) {
+                Ok(Some(commander)) => commander,
+                Ok(None) => {
+                    outcome.finished = true;
+                    break;
+                }
+                Err(_) => break,
+            };
+        let next = match commander.force_next_coordinate(|coord| walls.wall_state(coord)) {
+            Some(next) => next,
+            None => break,
+        };
+        distance += match state.update(&next) {
+            Some(TrajectoryKind::Front | TrajectoryKind::Back) => 1.0,
+            Some(TrajectoryKind::Left | TrajectoryKind::Right) => FRAC_PI_4,
+            None => break,
+        };
+        observe(&state, &mut walls);
+        if outcome.to_goal.is_none() {
+            visited.insert((state.x(), state.y()));
+            if goals.contains(&state.coordinate()) {
+                outcome.to_goal = Some(distance);
+                outcome.visited = visited.len();
+            }
+        }
+    }
+    outcome
+}
+
+fn benchmark<const W: u8>(name: &str, goals: [(u8, u8); 2], input: &str) {
+    let start = Coordinate::<W>::new(0, 1).unwrap();
+    let goals = goals.map(|(x, y)| Coordinate::<W>::new(x, y).unwrap());
+    let candidate = simulate(input, &goals, Searcher::new(start, &goals));
+    let adachi = simulate(input, &goals, AdachiSearcher::new(start, &goals));
+    let hands = [
+        simulate(input, &goals, HandSearcher::new(Hand::Left, &goals)),
+        simulate(input, &goals, HandSearcher::new(Hand::Right, &goals)),
+    ];
+    assert!(candidate.finished, "{}", name);
+    assert!(adachi.finished, "{}", name);
+    let to_goal = adachi.to_goal.unwrap_or_else(|| panic!("{}", name));
+
+    if W == 4 {
+        // the goals touch the outer walls, and a hand follower chosen without knowing the maze
+        // may take the longer way to them.
+        let to_goals = hands.map(|hand| hand.to_goal.unwrap_or_else(|| panic!("{}", name)));
+        assert!(to_goal <= to_goals[0].max(to_goals[1]), "{}", name);
+        assert!(
+            adachi.visited <= hands[0].visited.max(hands[1].visited),
+            "{}",
+            name
+        );
+    } else {
+        // the goals in the center are isolated from the outer walls.
+        assert!(hands.iter().all(|hand| hand.to_goal.is_none()), "{}", name);
+    }
+}
+
+#[test]
+fn test_strategy_distance() {
+    for (name, width, goals, input) in MAZES {
+        match width {
+            4 => benchmark::<4>(name, goals, input),
+            16 => benchmark::<16>(name, goals, input),
+            32 => benchmark::<32>(name, goals, input),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[test]
+fn test_unchecked_walls_are_open_for_adachi() {
+    let start = Coordinate::<4>::new(0, 1).unwrap();
+    let goals = [
+        Coordinate::new(2, 1).unwrap(),
+        Coordinate::new(3, 0).unwrap(),
+    ];
+    let mut strategy = AdachiSearcher::new(start, &goals);
+    let state = SearchState::new(start, Posture::North).unwrap();
+    let walls = Walls::<4>::new();
+    let commander = strategy
+        .next_commander(&state, |coord| walls.wall_state(coord), |_| 0)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        commander
+            .next_coordinate(|_| WallState::Checked { exists: false })
+            .unwrap(),
+        Coordinate::new(1, 2)
+    );
+}
@@ -24,7 +24,7 @@ where
 
     #[inline]
     fn get_lower_bits(&self, val: u32) -> u16 {
-        (val & core::u16::MAX as u32) as u16
+        (val & u16::MAX as u32) as u16
     }
 }
 
@@ -42,12 +42,8 @@ where
         self.apply(self.ratio);
     }
 
-    pub fn apply(&mut self, mut duty_ratio: f32) {
-        if duty_ratio > 1.0 {
-            duty_ratio = 1.0;
-        } else if duty_ratio < 0.0 {
-            duty_ratio = 0.0;
-        }
+    pub fn apply(&mut self, duty_ratio: f32) {
+        let duty_ratio = duty_ratio.clamp(0.0, 1.0);
         self.tim_pin.set_duty(self.tim_pin.get_max_duty());
         self.tim_pin
             .set_duty((duty_ratio * self.tim_pin.get_max_duty() as f32) as u16);
@@ -55,7 +51,7 @@ where
 
     #[allow(unused)]
     fn update_value(&mut self) {
-        self.value = block!(self.adc.read(&mut self.adc_pin)).unwrap() as u16;
+        self.value = block!(self.adc.read(&mut self.adc_pin)).unwrap();
     }
 
     #[allow(unused)]
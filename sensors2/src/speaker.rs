@@ -20,12 +20,8 @@ impl<P> Speaker<P>
 where
     P: PwmPin<Duty = u16>,
 {
-    pub fn apply(&mut self, mut duty_ratio: f32) {
-        if duty_ratio > 1.0 {
-            duty_ratio = 1.0;
-        } else if duty_ratio < 0.0 {
-            duty_ratio = 0.0;
-        }
+    pub fn apply(&mut self, duty_ratio: f32) {
+        let duty_ratio = duty_ratio.clamp(0.0, 1.0);
         self.pwm_pin
             .set_duty((duty_ratio * self.pwm_pin.get_max_duty() as f32) as u16);
     }